@@ -1,9 +1,7 @@
-use once_cell::sync::Lazy;
-use regex::Regex;
 use std::collections::HashSet;
 use std::fs;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum Tile {
     NS,
     EW,
@@ -15,6 +13,36 @@ enum Tile {
     Start,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Dir {
+    N,
+    E,
+    S,
+    W,
+}
+
+impl Dir {
+    fn opposite(&self) -> Dir {
+        match self {
+            Dir::N => Dir::S,
+            Dir::E => Dir::W,
+            Dir::S => Dir::N,
+            Dir::W => Dir::E,
+        }
+    }
+
+    fn step(&self, (i, j): (usize, usize)) -> Option<(usize, usize)> {
+        match self {
+            Dir::N => Some((i.checked_sub(1)?, j)),
+            Dir::E => Some((i, j + 1)),
+            Dir::S => Some((i + 1, j)),
+            Dir::W => Some((i, j.checked_sub(1)?)),
+        }
+    }
+}
+
+static PIPES: [Tile; 6] = [Tile::NS, Tile::EW, Tile::NE, Tile::NW, Tile::SW, Tile::SE];
+
 impl Tile {
     fn new(c: char) -> Option<Self> {
         match c {
@@ -29,178 +57,285 @@ impl Tile {
             _ => None,
         }
     }
-}
 
-fn parse_line(line: &str) -> Vec<Tile> {
-    line.chars().map(|c| Tile::new(c).unwrap()).collect()
-}
-
-fn parse_input(input: &str) -> Vec<Vec<Tile>> {
-    input.lines().map(parse_line).collect::<Vec<_>>()
-}
+    fn symbol(&self) -> char {
+        match self {
+            Self::NS => '|',
+            Self::EW => '-',
+            Self::NE => 'L',
+            Self::NW => 'J',
+            Self::SW => '7',
+            Self::SE => 'F',
+            Self::G => '.',
+            Self::Start => 'S',
+        }
+    }
 
-fn loop_path(sketch: Vec<Vec<Tile>>) -> Vec<(usize, usize)> {
-    let (mut i, mut j) = find_start(&sketch).expect("unable to find start");
-
-    println!("start: {:?}", (i, j));
-
-    let mut next_tiles = vec![
-        (i, j + 1, Tile::EW),
-        (i, j + 1, Tile::NW),
-        (i, j + 1, Tile::SW),
-        (i + 1, j, Tile::NS),
-        (i + 1, j, Tile::NE),
-        (i + 1, j, Tile::NW),
-    ];
-
-    if i > 0 {
-        next_tiles.append(&mut vec![
-            (i - 1, j, Tile::NS),
-            (i - 1, j, Tile::SW),
-            (i - 1, j, Tile::SE),
-        ])
+    fn connections(&self) -> Option<[Dir; 2]> {
+        match self {
+            Self::NS => Some([Dir::N, Dir::S]),
+            Self::EW => Some([Dir::E, Dir::W]),
+            Self::NE => Some([Dir::N, Dir::E]),
+            Self::NW => Some([Dir::N, Dir::W]),
+            Self::SW => Some([Dir::S, Dir::W]),
+            Self::SE => Some([Dir::S, Dir::E]),
+            Self::G | Self::Start => None,
+        }
     }
 
-    if j > 0 {
-        next_tiles.append(&mut vec![
-            (i, j - 1, Tile::EW),
-            (i, j - 1, Tile::NE),
-            (i, j - 1, Tile::SE),
-        ])
+    fn connects(&self, d: Dir) -> bool {
+        self.connections().is_some_and(|c| c.contains(&d))
     }
+}
 
-    let (mut k, mut l, _) = next_tiles
-        .iter()
-        .find(|(k, l, t)| {
-            sketch
-                .get(*k)
-                .and_then(|o| o.get(*l))
-                .is_some_and(|tt| tt == t)
+fn parse_input(input: &str) -> Result<Vec<Vec<Tile>>, String> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            line.chars()
+                .enumerate()
+                .map(|(j, c)| {
+                    Tile::new(c).ok_or_else(|| format!("unexpected {c:?} at {:?}", (i, j)))
+                })
+                .collect()
         })
-        .expect("unable to find direction from start");
-
-    let mut path = Vec::new();
-    loop {
-        path.push((i, j));
+        .collect()
+}
 
-        // println!("next: {:?}", (k, l));
+fn find_start(sketch: &[Vec<Tile>]) -> Option<(usize, usize)> {
+    for (i, row) in sketch.iter().enumerate() {
+        for (j, tile) in row.iter().enumerate() {
+            if tile == &Tile::Start {
+                return Some((i, j));
+            }
+        }
+    }
+    None
+}
 
-        let tile = sketch
-            .get(k)
-            .and_then(|o| o.get(l))
-            .expect("next tile does not exist");
+fn get(sketch: &[Vec<Tile>], (i, j): (usize, usize)) -> Option<Tile> {
+    sketch.get(i).and_then(|row| row.get(j)).copied()
+}
 
-        if tile == &Tile::Start {
-            break;
+// follows the pipes out of the start through `exits.0`, expecting to come
+// back in through `exits.1`
+fn walk(
+    sketch: &[Vec<Tile>],
+    start: (usize, usize),
+    exits: (Dir, Dir),
+) -> Result<Vec<(usize, usize)>, String> {
+    let mut path = vec![start];
+    let (mut position, mut heading) = (start, exits.0);
+    loop {
+        let next = heading
+            .step(position)
+            .filter(|p| get(sketch, *p).is_some())
+            .ok_or_else(|| {
+                format!("walk ran off the sketch heading {heading:?} from {position:?}")
+            })?;
+        let tile = get(sketch, next).unwrap();
+        let from = heading.opposite();
+        if tile == Tile::Start {
+            if from != exits.1 {
+                return Err(format!(
+                    "walk came back to the start from the {from:?}, not the {:?}",
+                    exits.1
+                ));
+            }
+            return Ok(path);
         }
 
-        let (m, n) = match (k as i32 - i as i32, l as i32 - j as i32) {
-            (-1, 0) => match tile {
-                Tile::NS => (k - 1, l),
-                Tile::SW => (k, l - 1),
-                Tile::SE => (k, l + 1),
-                _ => panic!(),
-            },
-            (0, 1) => match tile {
-                Tile::EW => (k, l + 1),
-                Tile::NW => (k - 1, l),
-                Tile::SW => (k + 1, l),
-                _ => panic!(),
-            },
-            (1, 0) => match tile {
-                Tile::NS => (k + 1, l),
-                Tile::NE => (k, l + 1),
-                Tile::NW => (k, l - 1),
-                _ => panic!(),
-            },
-            (0, -1) => match tile {
-                Tile::EW => (k, l - 1),
-                Tile::NE => (k - 1, l),
-                Tile::SE => (k + 1, l),
-                _ => panic!(),
-            },
-            _ => panic!(),
-        };
-
-        (i, j) = (k, l);
-        (k, l) = (m, n);
+        if !tile.connects(from) {
+            return Err(format!(
+                "walk stopped at {next:?}: {:?} has no opening to the {from:?}",
+                tile.symbol()
+            ));
+        }
+        heading = tile
+            .connections()
+            .unwrap()
+            .into_iter()
+            .find(|d| *d != from)
+            .unwrap();
+        position = next;
+        path.push(position);
     }
+}
 
-    path
+#[derive(Debug)]
+struct PipeLoop {
+    // in walking order, beginning at the start
+    path: Vec<(usize, usize)>,
+    // the pipe hidden under the start
+    start_tile: Tile,
+    enclosed: Vec<(usize, usize)>,
+    junk: Vec<(usize, usize)>,
 }
 
-fn find_start(sketch: &Vec<Vec<Tile>>) -> Option<(usize, usize)> {
-    for (i, row) in sketch.iter().enumerate() {
-        for (j, tile) in row.iter().enumerate() {
-            if tile == &Tile::Start {
-                return Some((i, j));
+impl PipeLoop {
+    fn new(sketch: &[Vec<Tile>]) -> Result<Self, String> {
+        let start = find_start(sketch).ok_or("unable to find start")?;
+
+        let exits: Vec<Dir> = [Dir::N, Dir::E, Dir::S, Dir::W]
+            .into_iter()
+            .filter(|d| {
+                d.step(start)
+                    .and_then(|p| get(sketch, p))
+                    .is_some_and(|t| t.connects(d.opposite()))
+            })
+            .collect();
+        // junk pipes may point into the start as well, so try each pair of
+        // openings until one of them closes the loop
+        let mut walks = exits
+            .iter()
+            .enumerate()
+            .flat_map(|(k, a)| exits[k + 1..].iter().map(move |b| (*a, *b)))
+            .map(|pair| walk(sketch, start, pair).map(|path| (pair, path)));
+        let mut first_error = None;
+        let ((a, b), path) = loop {
+            match walks.next() {
+                Some(Ok(found)) => break found,
+                Some(Err(e)) => {
+                    first_error.get_or_insert(e);
+                }
+                None => {
+                    return Err(first_error.unwrap_or_else(|| {
+                        format!("start at {start:?} joins {} pipes, not 2", exits.len())
+                    }))
+                }
+            }
+        };
+        let start_tile = PIPES
+            .iter()
+            .copied()
+            .find(|t| t.connects(a) && t.connects(b))
+            .unwrap();
+
+        let on_loop: HashSet<(usize, usize)> = path.iter().copied().collect();
+        let mut enclosed = Vec::new();
+        let mut junk = Vec::new();
+        for (i, row) in sketch.iter().enumerate() {
+            // crossing a loop pipe that reaches north flips inside and outside
+            let mut inside = false;
+            for (j, tile) in row.iter().enumerate() {
+                if on_loop.contains(&(i, j)) {
+                    let tile = if *tile == Tile::Start {
+                        start_tile
+                    } else {
+                        *tile
+                    };
+                    inside ^= tile.connects(Dir::N);
+                    continue;
+                }
+                if inside {
+                    enclosed.push((i, j));
+                }
+                if tile.connections().is_some() {
+                    junk.push((i, j));
+                }
             }
         }
+
+        Ok(PipeLoop {
+            path,
+            start_tile,
+            enclosed,
+            junk,
+        })
+    }
+
+    fn farthest(&self) -> usize {
+        self.path.len() / 2
     }
-    None
 }
 
-fn build_map(input: &str, path: Vec<(usize, usize)>) -> Vec<String> {
-    let path_set = path.iter().collect::<HashSet<_>>();
+fn build_map(sketch: &[Vec<Tile>], pipe_loop: &PipeLoop) -> Vec<String> {
+    let path_set = pipe_loop.path.iter().collect::<HashSet<_>>();
 
-    input
-        .lines()
+    sketch
+        .iter()
         .enumerate()
-        .map(|(i, line)| {
-            line.chars()
+        .map(|(i, row)| {
+            row.iter()
                 .enumerate()
-                .map(|(j, c)| {
-                    if path_set.contains(&(i, j)) {
-                        if c == 'S' {
-                            '-'
-                            // '7'
-                        } else {
-                            c
-                        }
-                    } else {
-                        '.'
-                    }
+                .map(|(j, tile)| match tile {
+                    _ if !path_set.contains(&(i, j)) => '.',
+                    Tile::Start => pipe_loop.start_tile.symbol(),
+                    _ => tile.symbol(),
                 })
                 .collect::<String>()
         })
         .collect::<Vec<_>>()
 }
 
-fn count_enclosed(path_map: &Vec<String>) -> usize {
-    static PATTERN: Lazy<Regex> = Lazy::new(|| Regex::new(r"(\||F-*J|L-*7)").unwrap());
+#[test]
+fn test_pipe_loop() {
+    let input = "7-F7-
+.FJ|7
+SJLL7
+|F--J
+LJ.LJ
+";
+    let pipe_loop = PipeLoop::new(&parse_input(input).unwrap()).unwrap();
+    assert_eq!(pipe_loop.farthest(), 8);
+    assert_eq!(pipe_loop.start_tile, Tile::SE);
+    assert_eq!(&pipe_loop.path[..3], &[(2, 0), (2, 1), (1, 1)]);
+    assert_eq!(
+        pipe_loop.junk,
+        vec![(0, 0), (0, 1), (0, 4), (1, 4), (2, 2), (4, 3), (4, 4)]
+    );
+    assert_eq!(pipe_loop.enclosed, vec![(2, 2)]);
 
-    path_map
-        .iter()
-        .flat_map(|line| {
-            line.chars().enumerate().filter(|(i, c)| {
-                if c == &'.' {
-                    let substr = &line[0..*i];
-                    let crossings = PATTERN.find_iter(substr).count();
-                    crossings % 2 == 1
-                } else {
-                    false
-                }
-            })
-        })
-        .count()
-}
+    let input = "FF7FSF7F7F7F7F7F---7
+L|LJ||||||||||||F--J
+FL-7LJLJ||||||LJL-77
+F--JF--7||LJLJ7F7FJ-
+L---JF-JLJ.||-FJLJJ7
+|F|F-JF---7F7-L7L|7|
+|FFJF7L7F-JF7|JL---7
+7-L-JL7||F7|L7F-7F7|
+L.L7LFJ|||||FJL7||LJ
+L7JLJL-JLJLJL--JLJ.L
+";
+    let sketch = parse_input(input).unwrap();
+    let pipe_loop = PipeLoop::new(&sketch).unwrap();
+    assert_eq!(pipe_loop.enclosed.len(), 10);
+    assert_eq!(pipe_loop.start_tile, Tile::SW);
 
-#[test]
-fn test_things() {
-    static PATTERN: Lazy<Regex> = Lazy::new(|| Regex::new(r"(\||F-*J|L-*7)").unwrap());
-    let crossings = PATTERN.find_iter("...F-JF---7..|L7").count();
-    assert_eq!(crossings, 3);
+    let broken = parse_input("F-7\n|.-\nS-J\n").unwrap();
+    assert_eq!(
+        PipeLoop::new(&broken).unwrap_err(),
+        "walk stopped at (1, 2): '-' has no opening to the N"
+    );
+    assert!(PipeLoop::new(&parse_input("S-7\n|.|\nL-|\n").unwrap())
+        .unwrap_err()
+        .starts_with("walk ran off the sketch heading S from (2, 2)"));
+    assert!(parse_input("S-X").is_err());
+
+    // a junk pipe pointing into the start doesn't stop the loop being found
+    let sketch = parse_input(".F-7\n-S.|\n.L-J\n").unwrap();
+    let pipe_loop = PipeLoop::new(&sketch).unwrap();
+    assert_eq!(pipe_loop.start_tile, Tile::NS);
+    assert_eq!(pipe_loop.junk, vec![(1, 0)]);
+    assert_eq!(pipe_loop.enclosed, vec![(1, 2)]);
+    assert_eq!(build_map(&sketch, &pipe_loop), vec![".F-7", ".|.|", ".L-J"]);
 }
 
 fn main() {
     let input = fs::read_to_string("input").expect("unable to read input");
-    let sketch = parse_input(&input);
-    let path = loop_path(sketch);
-    let path_map = build_map(&input, path);
+    let sketch = parse_input(&input).expect("unable to parse input");
+    let pipe_loop = PipeLoop::new(&sketch).expect("unable to follow loop");
+    let path_map = build_map(&sketch, &pipe_loop);
 
     println!("{}", path_map.join("\n"));
+    println!(
+        "farthest: {}, junk pipes: {}",
+        pipe_loop.farthest(),
+        pipe_loop.junk.len()
+    );
 
-    let answer = count_enclosed(&path_map);
+    let answer = pipe_loop.enclosed.len();
 
     println!("answer: {answer}");
 }
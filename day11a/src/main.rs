@@ -1,4 +1,4 @@
-use std::fs;
+use std::{collections::BinaryHeap, env, fs};
 
 fn get_locations(input: &str) -> Vec<(i64, i64)> {
     input
@@ -19,50 +19,264 @@ fn get_locations(input: &str) -> Vec<(i64, i64)> {
         .collect::<Vec<_>>()
 }
 
-fn adjust_locations(locations: &mut Vec<(i64, i64)>) {
+// every empty row or column becomes `factor` of them
+fn adjust_locations(locations: &mut [(i64, i64)], factor: i64) {
     let max_row = locations.iter().map(|l| l.0).max().unwrap();
     let max_col = locations.iter().map(|l| l.1).max().unwrap();
 
     for i in (0..max_row).rev() {
-        if locations.iter().find(|(x, _)| x == &i).is_none() {
+        if !locations.iter().any(|(x, _)| x == &i) {
             for (x, _) in locations.iter_mut() {
                 if *x > i {
-                    *x += 999_999;
+                    *x += factor - 1;
                 }
             }
         }
     }
 
     for i in (0..max_col).rev() {
-        if locations.iter().find(|(_, x)| x == &i).is_none() {
+        if !locations.iter().any(|(_, x)| x == &i) {
             for (_, x) in locations.iter_mut() {
                 if *x > i {
-                    *x += 999_999;
+                    *x += factor - 1;
                 }
             }
         }
     }
 }
 
-fn shortest_distances(locations: &Vec<(i64, i64)>) -> i64 {
-    let mut total_distance = 0;
-    for i in 0..locations.len() {
-        for j in 0..locations.len() {
-            let (r0, c0) = locations[i];
-            let (r1, c1) = locations[j];
+fn distance((r0, c0): (i64, i64), (r1, c1): (i64, i64)) -> i64 {
+    (r1 - r0).abs() + (c1 - c0).abs()
+}
+
+// rows and columns add up separately; once sorted, each value is
+// farther than everything before it by exactly the difference
+fn shortest_distances(locations: &[(i64, i64)]) -> i64 {
+    let axis_total = |mut values: Vec<i64>| {
+        values.sort();
+        let mut before = 0;
+        let mut total = 0;
+        for (i, v) in values.iter().enumerate() {
+            total += v * i as i64 - before;
+            before += v;
+        }
+        total
+    };
+
+    axis_total(locations.iter().map(|l| l.0).collect())
+        + axis_total(locations.iter().map(|l| l.1).collect())
+}
+
+// galaxies are numbered in reading order; `by_row` lets a search stop as
+// soon as the rows alone are farther apart than the best distance so far
+struct GalaxyIndex {
+    galaxies: Vec<(i64, i64)>,
+    by_row: Vec<usize>,
+}
+
+impl GalaxyIndex {
+    fn new(galaxies: Vec<(i64, i64)>) -> Self {
+        let mut by_row: Vec<usize> = (0..galaxies.len()).collect();
+        by_row.sort_by_key(|g| galaxies[*g]);
+        GalaxyIndex { galaxies, by_row }
+    }
+
+    // the closest other galaxy to each galaxy, with its distance
+    fn nearest(&self) -> Vec<Option<(usize, i64)>> {
+        let mut nearest = vec![None; self.galaxies.len()];
+        for (p, a) in self.by_row.iter().enumerate() {
+            let here = self.galaxies[*a];
+            let mut best: Option<(usize, i64)> = None;
+            let below = self.by_row[p + 1..].iter();
+            let above = self.by_row[..p].iter().rev();
+
+            for side in [below.collect::<Vec<_>>(), above.collect()] {
+                for b in side {
+                    let there = self.galaxies[*b];
+                    if best.is_some_and(|(_, d)| (there.0 - here.0).abs() > d) {
+                        break;
+                    }
+                    let d = distance(here, there);
+                    if best.is_none_or(|(c, e)| (d, *b) < (e, c)) {
+                        best = Some((*b, d));
+                    }
+                }
+            }
+            nearest[*a] = best;
+        }
+        nearest
+    }
+
+    // the k pairs with the smallest distances, closest first
+    fn closest_pairs(&self, k: usize) -> Vec<(i64, usize, usize)> {
+        let mut heap: BinaryHeap<(i64, usize, usize)> = BinaryHeap::new();
+        for (p, a) in self.by_row.iter().enumerate() {
+            let here = self.galaxies[*a];
+            for b in &self.by_row[p + 1..] {
+                let there = self.galaxies[*b];
+                if heap.len() == k && heap.peek().is_some_and(|(d, ..)| there.0 - here.0 > *d) {
+                    break;
+                }
+                heap.push((distance(here, there), *a.min(b), *a.max(b)));
+                if heap.len() > k {
+                    heap.pop();
+                }
+            }
+        }
+        heap.into_sorted_vec()
+    }
+
+    // the distance is the largest spread of r + c or r - c, so the
+    // extremes of those two sums are the only candidates
+    fn farthest_pair(&self) -> Option<(i64, usize, usize)> {
+        [1, -1]
+            .iter()
+            .filter_map(|s| {
+                let key = |g: &usize| self.galaxies[*g].0 + s * self.galaxies[*g].1;
+                let lo = (0..self.galaxies.len()).min_by_key(key)?;
+                let hi = (0..self.galaxies.len()).max_by_key(key)?;
+                Some((
+                    distance(self.galaxies[lo], self.galaxies[hi]),
+                    lo.min(hi),
+                    lo.max(hi),
+                ))
+            })
+            .max_by_key(|(d, ..)| *d)
+    }
 
-            total_distance += (r1 - r0).abs() + (c1 - c0).abs();
+    // pair counts per distance range; unlike the queries above, this has
+    // to look at every pair
+    fn histogram(&self, buckets: usize) -> Vec<(i64, i64, usize)> {
+        let Some((max, ..)) = self.farthest_pair() else {
+            return Vec::new();
+        };
+        let width = (max as usize + 1).div_ceil(buckets.max(1));
+        let mut counts = vec![0; (max as usize + 1).div_ceil(width)];
+        for (p, a) in self.galaxies.iter().enumerate() {
+            for b in &self.galaxies[p + 1..] {
+                counts[distance(*a, *b) as usize / width] += 1;
+            }
         }
+        counts
+            .into_iter()
+            .enumerate()
+            .map(|(i, n)| ((i * width) as i64, ((i + 1) * width - 1) as i64, n))
+            .collect()
+    }
+}
+
+#[test]
+fn test_galaxy_index() {
+    let input = "...#......
+.......#..
+#.........
+..........
+......#...
+.#........
+.........#
+..........
+.......#..
+#...#.....
+";
+    for (factor, expected) in [(2, 374), (10, 1030), (100, 8410)] {
+        let mut locations = get_locations(input);
+        adjust_locations(&mut locations, factor);
+        assert_eq!(shortest_distances(&locations), expected);
     }
 
-    total_distance / 2
+    let mut locations = get_locations(input);
+    adjust_locations(&mut locations, 2);
+    let index = GalaxyIndex::new(locations.clone());
+
+    let mut pairs: Vec<(i64, usize, usize)> = Vec::new();
+    for a in 0..locations.len() {
+        for b in a + 1..locations.len() {
+            pairs.push((distance(locations[a], locations[b]), a, b));
+        }
+    }
+    pairs.sort();
+
+    assert_eq!(index.closest_pairs(5), pairs[..5]);
+    assert_eq!(index.closest_pairs(100), pairs);
+    assert_eq!(
+        index.farthest_pair().map(|p| p.0),
+        pairs.last().map(|p| p.0)
+    );
+
+    for (a, nearest) in index.nearest().iter().enumerate() {
+        let best = pairs
+            .iter()
+            .filter(|(_, b, c)| *b == a || *c == a)
+            .map(|(d, b, c)| (*d, if *b == a { *c } else { *b }))
+            .min();
+        assert_eq!(nearest.map(|(b, d)| (d, b)), best);
+    }
+
+    // galaxies 2 and 8 sit 19 apart
+    assert_eq!(index.farthest_pair(), Some((19, 1, 7)));
+    let histogram = index.histogram(4);
+    assert_eq!(histogram.iter().map(|h| h.2).sum::<usize>(), 36);
+    assert_eq!(
+        histogram[0],
+        (0, 4, pairs.iter().filter(|p| p.0 <= 4).count())
+    );
 }
 
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let factor = match args.iter().position(|a| a == "--expansion") {
+        Some(k) => args
+            .get(k + 1)
+            .and_then(|n| n.parse().ok())
+            .expect("--expansion takes a number"),
+        None => 1_000_000,
+    };
+    let query: Vec<&str> = args
+        .iter()
+        .enumerate()
+        .filter(|(k, a)| !a.starts_with("--") && (*k == 0 || args[k - 1] != "--expansion"))
+        .map(|(_, a)| a.as_str())
+        .collect();
+    let number = |default: usize| {
+        query
+            .get(1)
+            .map_or(default, |n| n.parse().expect("expected a number"))
+    };
+
     let input = fs::read_to_string("input").expect("unable to read input");
     let mut locations = get_locations(&input);
-    adjust_locations(&mut locations);
-    let answer = shortest_distances(&locations);
+    adjust_locations(&mut locations, factor);
+    let index = GalaxyIndex::new(locations);
 
-    println!("answer: {answer}");
+    match query.first().copied() {
+        Some("nearest") => {
+            for (a, nearest) in index.nearest().iter().enumerate() {
+                if let Some((b, d)) = nearest {
+                    println!("galaxy {}: nearest is {} at {d}", a + 1, b + 1);
+                }
+            }
+        }
+        Some("closest") => {
+            for (d, a, b) in index.closest_pairs(number(10)) {
+                println!("galaxies {} and {}: {d}", a + 1, b + 1);
+            }
+        }
+        Some("farthest") => {
+            if let Some((d, a, b)) = index.farthest_pair() {
+                println!("galaxies {} and {}: {d}", a + 1, b + 1);
+            }
+        }
+        Some("histogram") => {
+            for (lo, hi, n) in index.histogram(number(10)) {
+                println!("{lo:>12} - {hi:<12} {n}");
+            }
+        }
+        Some("sum") | None => {
+            let answer = shortest_distances(&index.galaxies);
+            println!("answer: {answer}");
+        }
+        Some(other) => {
+            eprintln!("unknown query {other:?}; try sum, nearest, closest, farthest or histogram")
+        }
+    }
 }
@@ -1,9 +1,12 @@
 use std::{
-    collections::{BTreeSet, HashMap, HashSet},
-    fs,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    env, fs,
 };
 
-#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+static MIN: usize = 4;
+static MAX: usize = 10;
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Debug)]
 enum Dir {
     N,
     E,
@@ -11,7 +14,7 @@ enum Dir {
     W,
 }
 
-#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Debug)]
 struct Visit {
     i: usize,
     j: usize,
@@ -20,17 +23,15 @@ struct Visit {
 }
 
 fn get_neighbors(
-    map: &Vec<Vec<u32>>,
+    map: &[Vec<u32>],
     shape: (usize, usize),
     visited: &HashSet<Visit>,
-    i: usize,
-    j: usize,
-    dir: &Dir,
-    run: usize,
+    current: &Visit,
     heat_loss: u32,
 ) -> Vec<(u32, Visit)> {
-    static MIN: usize = 4;
-    static MAX: usize = 10;
+    let &Visit { i, j, ref dir, run } = current;
+    // the crucible can only stop at the end once it has moved MIN blocks
+    let at_end = |v: &Visit| v.i == shape.0 - 1 && v.j == shape.1 - 1;
     let mut unvisited = Vec::new();
     match dir {
         Dir::E => {
@@ -41,10 +42,8 @@ fn get_neighbors(
                     dir: Dir::E,
                     run: run + 1,
                 };
-                if !visited.contains(&v) {
-                    if !(i == shape.0 - 1 && j + 1 == shape.1 - 1) || run + 1 >= MIN {
-                        unvisited.push((heat_loss + map[i][j + 1], v));
-                    }
+                if !visited.contains(&v) && (!at_end(&v) || v.run >= MIN) {
+                    unvisited.push((heat_loss + map[i][j + 1], v));
                 }
             }
             if run >= MIN && i > 0 {
@@ -54,10 +53,8 @@ fn get_neighbors(
                     dir: Dir::N,
                     run: 1,
                 };
-                if !visited.contains(&v) {
-                    if !(i == shape.0 && j == shape.1 - 1) {
-                        unvisited.push((heat_loss + map[i - 1][j], v));
-                    }
+                if !visited.contains(&v) && (!at_end(&v) || v.run >= MIN) {
+                    unvisited.push((heat_loss + map[i - 1][j], v));
                 }
             }
             if run >= MIN && i < shape.0 - 1 {
@@ -67,10 +64,8 @@ fn get_neighbors(
                     dir: Dir::S,
                     run: 1,
                 };
-                if !visited.contains(&v) {
-                    if !(i + 1 == shape.0 - 1 && j == shape.1 - 1) {
-                        unvisited.push((heat_loss + map[i + 1][j], v));
-                    }
+                if !visited.contains(&v) && (!at_end(&v) || v.run >= MIN) {
+                    unvisited.push((heat_loss + map[i + 1][j], v));
                 }
             }
         }
@@ -82,10 +77,8 @@ fn get_neighbors(
                     dir: Dir::W,
                     run: run + 1,
                 };
-                if !visited.contains(&v) {
-                    if !(i == shape.0 - 1 && j == shape.1) || run + 1 >= MIN {
-                        unvisited.push((heat_loss + map[i][j - 1], v));
-                    }
+                if !visited.contains(&v) && (!at_end(&v) || v.run >= MIN) {
+                    unvisited.push((heat_loss + map[i][j - 1], v));
                 }
             }
             if run >= MIN && i > 0 {
@@ -95,10 +88,8 @@ fn get_neighbors(
                     dir: Dir::N,
                     run: 1,
                 };
-                if !visited.contains(&v) {
-                    if !(i == shape.0 && j == shape.1 - 1) {
-                        unvisited.push((heat_loss + map[i - 1][j], v));
-                    }
+                if !visited.contains(&v) && (!at_end(&v) || v.run >= MIN) {
+                    unvisited.push((heat_loss + map[i - 1][j], v));
                 }
             }
             if run >= MIN && i < shape.0 - 1 {
@@ -108,10 +99,8 @@ fn get_neighbors(
                     dir: Dir::S,
                     run: 1,
                 };
-                if !visited.contains(&v) {
-                    if !(i + 1 == shape.0 - 1 && j == shape.1 - 1) {
-                        unvisited.push((heat_loss + map[i + 1][j], v));
-                    }
+                if !visited.contains(&v) && (!at_end(&v) || v.run >= MIN) {
+                    unvisited.push((heat_loss + map[i + 1][j], v));
                 }
             }
         }
@@ -123,10 +112,8 @@ fn get_neighbors(
                     dir: Dir::N,
                     run: run + 1,
                 };
-                if !visited.contains(&v) {
-                    if !(i - 1 == shape.0 - 1 && j == shape.1 - 1) || run + 1 >= MIN {
-                        unvisited.push((heat_loss + map[i - 1][j], v));
-                    }
+                if !visited.contains(&v) && (!at_end(&v) || v.run >= MIN) {
+                    unvisited.push((heat_loss + map[i - 1][j], v));
                 }
             }
             if run >= MIN && j > 0 {
@@ -136,10 +123,8 @@ fn get_neighbors(
                     dir: Dir::W,
                     run: 1,
                 };
-                if !visited.contains(&v) {
-                    if !(i == shape.0 - 1 && j == shape.1) {
-                        unvisited.push((heat_loss + map[i][j - 1], v));
-                    }
+                if !visited.contains(&v) && (!at_end(&v) || v.run >= MIN) {
+                    unvisited.push((heat_loss + map[i][j - 1], v));
                 }
             }
             if run >= MIN && j < shape.1 - 1 {
@@ -149,10 +134,8 @@ fn get_neighbors(
                     dir: Dir::E,
                     run: 1,
                 };
-                if !visited.contains(&v) {
-                    if !(i == shape.0 - 1 && j + 1 == shape.1 - 1) {
-                        unvisited.push((heat_loss + map[i][j + 1], v));
-                    }
+                if !visited.contains(&v) && (!at_end(&v) || v.run >= MIN) {
+                    unvisited.push((heat_loss + map[i][j + 1], v));
                 }
             }
         }
@@ -164,10 +147,8 @@ fn get_neighbors(
                     dir: Dir::S,
                     run: run + 1,
                 };
-                if !visited.contains(&v) {
-                    if !(i + 1 == shape.0 - 1 && j == shape.1 - 1) || run + 1 >= MIN {
-                        unvisited.push((heat_loss + map[i + 1][j], v));
-                    }
+                if !visited.contains(&v) && (!at_end(&v) || v.run >= MIN) {
+                    unvisited.push((heat_loss + map[i + 1][j], v));
                 }
             }
             if run >= MIN && j > 0 {
@@ -177,10 +158,8 @@ fn get_neighbors(
                     dir: Dir::W,
                     run: 1,
                 };
-                if !visited.contains(&v) {
-                    if !(i == shape.0 - 1 && j == shape.1) {
-                        unvisited.push((heat_loss + map[i][j - 1], v));
-                    }
+                if !visited.contains(&v) && (!at_end(&v) || v.run >= MIN) {
+                    unvisited.push((heat_loss + map[i][j - 1], v));
                 }
             }
             if run >= MIN && j < shape.1 - 1 {
@@ -190,10 +169,8 @@ fn get_neighbors(
                     dir: Dir::E,
                     run: 1,
                 };
-                if !visited.contains(&v) {
-                    if !(i == shape.0 - 1 && j + 1 == shape.1 - 1) {
-                        unvisited.push((heat_loss + map[i][j + 1], v));
-                    }
+                if !visited.contains(&v) && (!at_end(&v) || v.run >= MIN) {
+                    unvisited.push((heat_loss + map[i][j + 1], v));
                 }
             }
         }
@@ -202,10 +179,11 @@ fn get_neighbors(
     unvisited
 }
 
-fn min_heat_loss(map: &Vec<Vec<u32>>) -> u32 {
+fn min_heat_loss(map: &[Vec<u32>]) -> (u32, Vec<Visit>) {
     let mut visited: HashSet<Visit> = HashSet::new();
     let mut unvisited: BTreeSet<(u32, Visit)> = BTreeSet::new();
     let mut heat_loss: HashMap<Visit, u32> = HashMap::new();
+    let mut previous: HashMap<Visit, Visit> = HashMap::new();
 
     let shape = (map.len(), map[0].len());
 
@@ -218,20 +196,16 @@ fn min_heat_loss(map: &Vec<Vec<u32>>) -> u32 {
     };
 
     loop {
-        if v.i == shape.0 - 1 && v.j == shape.0 - 1 {
+        if v.i == shape.0 - 1 && v.j == shape.1 - 1 {
             break;
         }
-        let neighbors = get_neighbors(&map, shape, &visited, v.i, v.j, &v.dir, v.run, h);
+        let neighbors = get_neighbors(map, shape, &visited, &v, h);
 
         // update unvisited and heat_loss
         for n in neighbors {
-            if let Some(h_old) = heat_loss.get(&n.1) {
-                if &n.0 < h_old {
-                    heat_loss.insert(n.1.clone(), n.0);
-                    unvisited.insert((n.0, n.1));
-                }
-            } else {
+            if heat_loss.get(&n.1).is_none_or(|h_old| &n.0 < h_old) {
                 heat_loss.insert(n.1.clone(), n.0);
+                previous.insert(n.1.clone(), v.clone());
                 unvisited.insert((n.0, n.1));
             }
         }
@@ -242,13 +216,99 @@ fn min_heat_loss(map: &Vec<Vec<u32>>) -> u32 {
         // find the next node to visit
         let next = unvisited.pop_first().unwrap();
         h = next.0;
-        v.i = next.1.i;
-        v.j = next.1.j;
-        v.dir = next.1.dir;
-        v.run = next.1.run;
+        v = next.1;
+    }
+
+    // walk back to the start, which is the only visit with no predecessor
+    let mut route = vec![v.clone()];
+    while let Some(p) = previous.get(&v) {
+        route.push(p.clone());
+        v = p.clone();
     }
+    route.pop();
+    route.reverse();
 
-    h
+    (h, route)
+}
+
+fn render_route(map: &[Vec<u32>], route: &[Visit]) -> String {
+    let mut grid: Vec<Vec<char>> = map
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|d| char::from_digit(*d, 10).unwrap())
+                .collect()
+        })
+        .collect();
+
+    for v in route {
+        grid[v.i][v.j] = match v.dir {
+            Dir::N => '^',
+            Dir::E => '>',
+            Dir::S => 'v',
+            Dir::W => '<',
+        };
+    }
+
+    grid.iter()
+        .map(|row| row.iter().collect::<String>() + "\n")
+        .collect()
+}
+
+fn run_lengths(route: &[Visit]) -> BTreeMap<usize, usize> {
+    let mut histogram = BTreeMap::new();
+    for (k, v) in route.iter().enumerate() {
+        // a straight segment ends wherever the next visit starts a new run
+        if route.get(k + 1).is_none_or(|next| next.run == 1) {
+            *histogram.entry(v.run).or_insert(0) += 1;
+        }
+    }
+    histogram
+}
+
+#[test]
+fn test_min_heat_loss() {
+    let map = parse_input(
+        "2413432311323
+3215453535623
+3255245654254
+3446585845452
+4546657867536
+1438598798454
+4457876987766
+3637877979653
+4654967986887
+4564679986453
+1224686865563
+2546548887735
+4322674655533
+",
+    );
+    let (h, route) = min_heat_loss(&map);
+    assert_eq!(h, 94);
+    assert_eq!(route.iter().map(|v| map[v.i][v.j]).sum::<u32>(), 94);
+    assert!(run_lengths(&route).keys().all(|r| (MIN..=MAX).contains(r)));
+
+    let map = parse_input(
+        "111111111111
+999999999991
+999999999991
+999999999991
+999999999991
+",
+    );
+    let (h, route) = min_heat_loss(&map);
+    assert_eq!(h, 71);
+    assert_eq!(run_lengths(&route), BTreeMap::from([(4, 2), (7, 1)]));
+    assert_eq!(
+        render_route(&map, &route),
+        "1>>>>>>>1111
+9999999v9991
+9999999v9991
+9999999v9991
+9999999v>>>>
+"
+    );
 }
 
 fn parse_input(input: &str) -> Vec<Vec<u32>> {
@@ -261,7 +321,17 @@ fn parse_input(input: &str) -> Vec<Vec<u32>> {
 fn main() {
     let input = fs::read_to_string("input").expect("unable to read input");
     let map = parse_input(&input);
-    let answer = min_heat_loss(&map);
+    let (answer, route) = min_heat_loss(&map);
+
+    if env::args().any(|a| a == "--route") {
+        print!("{}", render_route(&map, &route));
+        println!();
+        for (run, count) in run_lengths(&route) {
+            let ok = if (MIN..=MAX).contains(&run) { "" } else { " !" };
+            println!("run {run:>2}: {count}{ok}");
+        }
+        println!();
+    }
 
     println!("answer: {answer}")
 }
@@ -1,7 +1,12 @@
-use itertools::Itertools;
 use once_cell::sync::Lazy;
 use regex::{Match, Regex};
-use std::fs;
+use std::{env, fs};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    Plain,
+    Hex,
+}
 
 #[derive(Debug)]
 enum Dir {
@@ -15,207 +20,188 @@ enum Dir {
 struct Step {
     dir: Dir,
     length: i64,
+    color: (u8, u8, u8),
 }
 
 fn number_from_match(m: Match) -> Option<i64> {
     i64::from_str_radix(m.as_str(), 16).ok()
 }
 
-fn parse_input(input: &str) -> Vec<Step> {
+fn parse_input(input: &str, mode: Mode) -> Vec<Step> {
     input
         .lines()
         .flat_map(|l| -> Option<Step> {
             static PATTERN: Lazy<Regex> =
-                Lazy::new(|| Regex::new(r"([UDRL]) (\d+) \(#(.{5})(.{1})\)").unwrap());
+                Lazy::new(|| Regex::new(r"([UDRL]) (\d+) \(#((.{5})(.{1}))\)").unwrap());
 
             let captures = PATTERN.captures(l)?;
-            let dir = captures
-                .get(4)
-                .and_then(number_from_match)
-                .and_then(|n| match n {
-                    3 => Some(Dir::U),
-                    1 => Some(Dir::D),
-                    0 => Some(Dir::R),
-                    2 => Some(Dir::L),
-                    _ => None,
-                })?;
-            let length = captures.get(3).and_then(number_from_match)?;
-
-            Some(Step { dir, length })
+            let (dir, length) = match mode {
+                Mode::Plain => {
+                    let dir = captures.get(1).and_then(|c| match c.as_str() {
+                        "U" => Some(Dir::U),
+                        "D" => Some(Dir::D),
+                        "R" => Some(Dir::R),
+                        "L" => Some(Dir::L),
+                        _ => None,
+                    })?;
+                    let length = captures.get(2)?.as_str().parse::<i64>().ok()?;
+                    (dir, length)
+                }
+                Mode::Hex => {
+                    let dir =
+                        captures
+                            .get(5)
+                            .and_then(number_from_match)
+                            .and_then(|n| match n {
+                                3 => Some(Dir::U),
+                                1 => Some(Dir::D),
+                                0 => Some(Dir::R),
+                                2 => Some(Dir::L),
+                                _ => None,
+                            })?;
+                    let length = captures.get(4).and_then(number_from_match)?;
+                    (dir, length)
+                }
+            };
+
+            let rgb = captures.get(3).and_then(number_from_match)?;
+            let color = ((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8);
+
+            Some(Step { dir, length, color })
         })
         .collect()
 }
 
-#[derive(Debug, Eq, Ord, PartialEq, PartialOrd)]
-enum Or {
-    TL,
-    TR,
-    BL,
-    BR,
-}
-
-#[derive(Debug, Eq, Ord, PartialEq, PartialOrd)]
-struct Corner {
-    i: i64,
-    j: i64,
-    orientation: Or,
-}
-
-fn get_corners(steps: &[Step]) -> Option<Vec<Corner>> {
-    let mut corners: Vec<Corner> = Vec::new();
-
-    let mut i = 0;
-    let mut j = 0;
-    let mut d = &steps.last()?.dir;
+fn get_vertices(steps: &[Step]) -> Vec<(i128, i128)> {
+    let mut vertices = vec![(0, 0)];
+    let (mut i, mut j): (i128, i128) = (0, 0);
 
     for s in steps {
-        let orientation = match (d, &s.dir) {
-            (Dir::U, Dir::R) | (Dir::L, Dir::D) => Or::TL,
-            (Dir::R, Dir::D) | (Dir::U, Dir::L) => Or::TR,
-            (Dir::D, Dir::L) | (Dir::R, Dir::U) => Or::BR,
-            (Dir::L, Dir::U) | (Dir::D, Dir::R) => Or::BL,
-            _ => return None,
-        };
-        corners.push(Corner { i, j, orientation });
-
+        let length = s.length as i128;
         match s.dir {
-            Dir::U => i -= s.length,
-            Dir::D => i += s.length,
-            Dir::R => j += s.length,
-            Dir::L => j -= s.length,
+            Dir::U => i -= length,
+            Dir::D => i += length,
+            Dir::R => j += length,
+            Dir::L => j -= length,
         }
-        d = &s.dir;
+        vertices.push((i, j));
     }
-
-    corners.sort();
-
-    Some(corners)
+    vertices
 }
 
-fn get_area(corners: &[Corner]) -> Option<i64> {
-    let mut bounds: Vec<i64> = vec![];
-    let mut last_i_opt: Option<i64> = None;
-
-    let mut area = 0;
-    for (i, group) in &corners.into_iter().group_by(|Corner { i, .. }| i) {
-        // 1. add area from previous bounds, if any
-        if let Some(last_i) = last_i_opt {
-            let height = i - last_i;
-            let width: i64 = bounds
-                .iter()
-                .chunks(2)
-                .into_iter()
-                .flat_map(|mut chunk| {
-                    let left = chunk.next()?;
-                    let right = chunk.next()?;
-                    Some(right - left + 1)
-                })
-                .sum();
-            // println!("\nadding width {width} height {height}");
-            area += (width) * (height - 1);
-        }
-
-        // 2. add area from this row
-        let row: Vec<_> = group.collect();
-
-        // println!("old bounds: {:?}", bounds);
-        // println!("corners: {:?}", row);
-
-        let to_remove: Vec<i64> = row
-            .iter()
-            .filter(|Corner { orientation, .. }| orientation == &Or::BL || orientation == &Or::BR)
-            .map(|Corner { j, .. }| *j)
-            .collect();
-        let to_add: Vec<i64> = row
-            .iter()
-            .filter(|Corner { orientation, .. }| orientation == &Or::TL || orientation == &Or::TR)
-            .map(|Corner { j, .. }| *j)
-            .collect();
-        let mut new_bounds: Vec<_> = bounds
-            .iter()
-            .filter(|b| !to_remove.contains(b))
-            .chain(to_add.iter())
-            .map(|b| *b)
-            .collect();
-        new_bounds.sort();
-
-        let merged_bounds = get_merged_bounds(&bounds, &new_bounds)?;
-        area += merged_bounds.iter().map(|(a, b)| b - a + 1).sum::<i64>();
-
-        // 3. update bounds and set last_i
-        bounds = new_bounds;
-        // println!("new bounds {:?}", bounds);
-
-        last_i_opt = Some(*i);
-    }
+fn get_area(steps: &[Step]) -> i128 {
+    let vertices = get_vertices(steps);
+    let twice_area: i128 = vertices
+        .windows(2)
+        .map(|w| w[0].0 * w[1].1 - w[1].0 * w[0].1)
+        .sum();
+    let boundary: i128 = steps.iter().map(|s| s.length as i128).sum();
 
-    Some(area)
+    // Pick's theorem gives the interior points; the trench itself adds the boundary
+    twice_area.abs() / 2 + boundary / 2 + 1
 }
 
-fn bands(bounds: &[i64]) -> Vec<(i64, i64)> {
-    bounds
+fn render_svg(steps: &[Step], size: f64) -> Option<String> {
+    let vertices = get_vertices(steps);
+    let i_min = vertices.iter().map(|v| v.0).min()?;
+    let i_max = vertices.iter().map(|v| v.0).max()?;
+    let j_min = vertices.iter().map(|v| v.1).min()?;
+    let j_max = vertices.iter().map(|v| v.1).max()?;
+
+    // part 2 spans millions of meters, so squeeze the longer side down to `size`
+    let scale = size / (i_max - i_min).max(j_max - j_min).max(1) as f64;
+    let point = |(i, j): (i128, i128)| ((j - j_min) as f64 * scale, (i - i_min) as f64 * scale);
+
+    let (width, height) = point((i_max, j_max));
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"-2 -2 {:.2} {:.2}\">\n",
+        width + 4.0,
+        height + 4.0
+    );
+
+    let fill: Vec<String> = vertices
         .iter()
-        .chunks(2)
-        .into_iter()
-        .flat_map(|mut c| {
-            let l = c.next()?;
-            let r = c.next()?;
-            Some((*l, *r))
+        .map(|v| {
+            let (x, y) = point(*v);
+            format!("{x:.2},{y:.2}")
         })
-        .collect()
-}
+        .collect();
+    svg += &format!(
+        "<polygon points=\"{}\" fill=\"#d8d8d8\" stroke=\"none\"/>\n",
+        fill.join(" ")
+    );
 
-fn get_merged_bounds(a_bounds: &[i64], b_bounds: &[i64]) -> Option<Vec<(i64, i64)>> {
-    let mut merged_bounds = bands(a_bounds);
-    let b_bands = bands(b_bounds);
-
-    for b in b_bands {
-        // collect all items from merged_bounds that overlap at all with b
-        //
-        // overlap means a.1 >= b.0 and b.1 >= a.0
-        let b_vec = vec![b];
-        let overlap: Vec<_> = merged_bounds
-            .iter()
-            .filter(|a| a.1 >= b.0 && b.1 >= a.0)
-            .chain(b_vec.iter())
-            .collect();
-
-        // remove them from merged_bounds
-        let not_overlap: Vec<_> = merged_bounds
-            .iter()
-            .filter(|a| a.1 < b.0 || b.1 < a.0)
-            .collect();
-
-        // add a new item with min left and max right from the collected set
-        let l = *overlap.iter().map(|(x, _)| x).min()?;
-        let r = *overlap.iter().map(|(_, x)| x).max()?;
-        merged_bounds = not_overlap
-            .iter()
-            .map(|x| *x)
-            .chain([(l, r)].iter())
-            .map(|x| *x)
-            .collect();
+    for (s, w) in steps.iter().zip(vertices.windows(2)) {
+        let (x1, y1) = point(w[0]);
+        let (x2, y2) = point(w[1]);
+        let (r, g, b) = s.color;
+        svg += &format!(
+            "<line x1=\"{x1:.2}\" y1=\"{y1:.2}\" x2=\"{x2:.2}\" y2=\"{y2:.2}\" stroke=\"#{r:02x}{g:02x}{b:02x}\" stroke-width=\"2\" stroke-linecap=\"square\"/>\n"
+        );
     }
 
-    Some(merged_bounds)
+    svg += "</svg>\n";
+    Some(svg)
 }
 
 #[test]
-fn test_get_merged_bounds() {
-    assert_eq!(get_merged_bounds(&[1, 4], &[3, 6]), Some(vec![(1, 6)]));
-    assert_eq!(get_merged_bounds(&[3, 6], &[1, 4]), Some(vec![(1, 6)]));
-    assert_eq!(get_merged_bounds(&[1, 6], &[3, 4]), Some(vec![(1, 6)]));
-    assert_eq!(
-        get_merged_bounds(&[1, 4], &[5, 6]),
-        Some(vec![(1, 4), (5, 6)])
-    );
+fn test_get_area() {
+    let input = "R 6 (#70c710)
+D 5 (#0dc571)
+L 2 (#5713f0)
+D 2 (#d2c081)
+R 2 (#59c680)
+D 2 (#411b91)
+L 5 (#8ceee2)
+U 2 (#caa173)
+L 1 (#1b58a2)
+U 2 (#caa171)
+R 2 (#7807d2)
+U 3 (#a77fa3)
+L 2 (#015232)
+U 2 (#7a21e3)
+";
+    assert_eq!(get_area(&parse_input(input, Mode::Plain)), 62);
+    assert_eq!(get_area(&parse_input(input, Mode::Hex)), 952408144115);
+
+    // consecutive steps in the same direction are just a longer edge
+    let square = "R 1 (#000000)
+R 1 (#000000)
+D 2 (#000000)
+L 2 (#000000)
+U 2 (#000000)
+";
+    assert_eq!(get_area(&parse_input(square, Mode::Plain)), 9);
+}
+
+#[test]
+fn test_render_svg() {
+    let square = "R 2 (#ff0000)
+D 2 (#00ff00)
+L 2 (#0000ff)
+U 2 (#102030)
+";
+    let svg = render_svg(&parse_input(square, Mode::Plain), 100.0).unwrap();
+    assert!(svg.contains("points=\"0.00,0.00 100.00,0.00 100.00,100.00 0.00,100.00 0.00,0.00\""));
+    assert!(svg.contains("x1=\"0.00\" y1=\"0.00\" x2=\"100.00\" y2=\"0.00\" stroke=\"#ff0000\""));
+    assert!(svg.contains("stroke=\"#102030\""));
+    assert_eq!(svg.matches("<line").count(), 4);
 }
 
 fn main() {
+    let mode = if env::args().any(|a| a == "--plain") {
+        Mode::Plain
+    } else {
+        Mode::Hex
+    };
     let input = fs::read_to_string("input").expect("unable to read input");
-    let steps = parse_input(&input);
-    let corners = get_corners(&steps).expect("unable to trace path");
-    let answer = get_area(&corners).expect("unable to get area");
+    let steps = parse_input(&input, mode);
+    let answer = get_area(&steps);
+
+    if env::args().any(|a| a == "--svg") {
+        let svg = render_svg(&steps, 1000.0).expect("no steps to render");
+        fs::write("trench.svg", svg).expect("unable to write trench.svg");
+    }
 
     println!("answer: {answer}");
 }
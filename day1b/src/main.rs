@@ -1,74 +1,178 @@
-use once_cell::sync::Lazy;
-use regex::Regex;
-use std::fs;
-
-fn parse<'a>(token: &'a str) -> &'a str {
-    match token {
-        "one" => "1",
-        "two" => "2",
-        "three" => "3",
-        "four" => "4",
-        "five" => "5",
-        "six" => "6",
-        "seven" => "7",
-        "eight" => "8",
-        "nine" => "9",
-        t => t,
-    }
+use aho_corasick::AhoCorasick;
+use std::{env, fs};
+
+struct Scanner {
+    automaton: AhoCorasick,
+    values: Vec<u32>,
 }
 
-fn find<I: Iterator<Item = usize>>(line: &str, range: I) -> Option<&str> {
-    static PATTERN: Lazy<Regex> = Lazy::new(|| {
-        Regex::new(r"^(one|two|three|four|five|six|seven|eight|nine|1|2|3|4|5|6|7|8|9|0)").unwrap()
-    });
+fn english() -> Vec<(String, u32)> {
+    let names = [
+        "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+    ];
+    names
+        .iter()
+        .zip(1..)
+        .map(|(n, d)| (n.to_string(), d))
+        .collect()
+}
 
-    for i in range {
-        let sub_line = &line[i..];
-        if let Some(m) = PATTERN.find(sub_line) {
-            return Some(m.as_str());
+// one `token digit` pair per line; blank lines and `#` comments are skipped
+fn load_vocabulary(text: &str) -> Result<Vec<(String, u32)>, String> {
+    let mut vocabulary: Vec<(String, u32)> = Vec::new();
+    for (k, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
         }
-    }
+        let (token, value) = match line.split_whitespace().collect::<Vec<_>>()[..] {
+            [token, value] => (token, value),
+            _ => {
+                return Err(format!(
+                    "line {}: expected `token digit`, got {line:?}",
+                    k + 1
+                ))
+            }
+        };
+        if token.chars().all(|c| c.is_ascii_digit()) {
+            return Err(format!(
+                "line {}: {token:?} is made of digits, which always stand for themselves",
+                k + 1
+            ));
+        }
+        let value = value
+            .parse::<u32>()
+            .ok()
+            .filter(|d| *d < 10)
+            .ok_or(format!("line {}: {value:?} is not a digit", k + 1))?;
 
-    None
+        match vocabulary.iter().find(|(t, _)| t == token) {
+            Some((_, v)) if *v != value => {
+                return Err(format!("line {}: {token} is already mapped to {v}", k + 1))
+            }
+            Some(_) => {}
+            None => vocabulary.push((token.to_string(), value)),
+        }
+    }
+    Ok(vocabulary)
 }
 
-fn find_forward(line: &str) -> Option<&str> {
-    find(line, 0..line.len())
-}
+#[test]
+fn test_load_vocabulary() {
+    let vocabulary = load_vocabulary("# german\neins 1\nzwei 2\n\neins 1\n").unwrap();
+    assert_eq!(
+        vocabulary,
+        vec![("eins".to_string(), 1), ("zwei".to_string(), 2)]
+    );
 
-fn find_backward(line: &str) -> Option<&str> {
-    find(line, (0..line.len()).rev())
+    assert!(load_vocabulary("eins 1\neins 2\n").is_err());
+    assert!(load_vocabulary("zehn 10\n").is_err());
+    assert!(load_vocabulary("drei\n").is_err());
+    assert_eq!(
+        load_vocabulary("eins 1\n7 8\n"),
+        Err("line 2: \"7\" is made of digits, which always stand for themselves".to_string())
+    );
 }
 
-fn digits(line: &str) -> i32 {
-    // println!("{line}");
+impl Scanner {
+    // the digit characters are always tokens; `words` adds spelled-out ones
+    fn new(words: &[(String, u32)]) -> Scanner {
+        let mut tokens: Vec<(String, u32)> = (0..10).map(|d| (d.to_string(), d)).collect();
+        tokens.extend(words.iter().cloned());
+
+        let automaton =
+            AhoCorasick::new(tokens.iter().map(|(t, _)| t)).expect("unable to build automaton");
+        let values = tokens.iter().map(|(_, d)| *d).collect();
+        Scanner { automaton, values }
+    }
+
+    // overlapping matches come out in one forward pass, so "eightwo" yields
+    // both eight and two; the earliest and latest starts win, longest first
+    fn first_and_last(&self, line: &str) -> Option<(u32, u32)> {
+        let mut first: Option<(usize, usize, u32)> = None;
+        let mut last: Option<(usize, usize, u32)> = None;
 
-    let left_raw = find_forward(line).expect("no left digit found");
-    let left_digit = parse(left_raw);
-    // println!("left digit: {left_digit}");
+        for m in self.automaton.find_overlapping_iter(line) {
+            let (start, len) = (m.start(), m.len());
+            let value = self.values[m.pattern().as_usize()];
+            if first.is_none_or(|(s, l, _)| start < s || (start == s && len > l)) {
+                first = Some((start, len, value));
+            }
+            if last.is_none_or(|(s, l, _)| start > s || (start == s && len > l)) {
+                last = Some((start, len, value));
+            }
+        }
+
+        Some((first?.2, last?.2))
+    }
+}
+
+fn digits(scanner: &Scanner, line: &str) -> Result<u32, String> {
+    let (left_digit, right_digit) = scanner
+        .first_and_last(line)
+        .ok_or(format!("no digit token in {line:?}"))?;
+    Ok(left_digit * 10 + right_digit)
+}
 
-    let right_raw = find_backward(line).expect("no right digit found");
-    let right_digit = parse(right_raw);
-    // println!("right digit: {right_digit}");
+#[test]
+fn test_digits() {
+    let scanner = Scanner::new(&english());
+    assert_eq!(digits(&scanner, "two1nine"), Ok(29));
+    assert_eq!(digits(&scanner, "eightwothree"), Ok(83));
+    assert_eq!(digits(&scanner, "abcone2threexyz"), Ok(13));
+    assert_eq!(digits(&scanner, "xtwone3four"), Ok(24));
+    assert_eq!(digits(&scanner, "4nineeightseven2"), Ok(42));
+    assert_eq!(digits(&scanner, "zoneight234"), Ok(14));
+    assert_eq!(digits(&scanner, "7pqrstsixteen"), Ok(76));
+    assert_eq!(digits(&scanner, "eightwo"), Ok(82));
+    assert_eq!(digits(&scanner, "oneight"), Ok(18));
 
-    let number = format!("{left_digit}{right_digit}")
-        .parse::<i32>()
-        .expect("unable to parse as integer");
-    // println!("{number}");
+    let scanner = Scanner::new(&[]);
+    assert_eq!(digits(&scanner, "pqr3stu8vwx"), Ok(38));
+    assert_eq!(digits(&scanner, "treb7uchet"), Ok(77));
+    assert_eq!(digits(&scanner, "oneight5"), Ok(55));
+    assert!(digits(&scanner, "oneight").is_err());
 
-    number
+    // prefix-sharing tokens resolve to the longest one at the same position
+    let scanner =
+        Scanner::new(&load_vocabulary("sept 7\nseptante 7\ndeux 2\nhuit 8\nhuitante 8\n").unwrap());
+    assert_eq!(digits(&scanner, "xseptantedeux"), Ok(72));
+    assert_eq!(digits(&scanner, "deuxhuitante"), Ok(28));
+    assert_eq!(digits(&scanner, "huitseptdeux"), Ok(82));
 }
 
 fn main() {
+    let args: Vec<String> = env::args().collect();
+    let words = if args.iter().any(|a| a == "--digits") {
+        Vec::new()
+    } else if let Some(k) = args.iter().position(|a| a == "--vocab") {
+        let path = args.get(k + 1).expect("--vocab takes a file name");
+        let text = fs::read_to_string(path).expect("unable to read vocabulary");
+        load_vocabulary(&text).expect("unable to load vocabulary")
+    } else {
+        english()
+    };
+
     let input_path = "input";
     let input = fs::read_to_string(input_path).expect("unable to read input");
-
-    // let _: Vec<_> = input.lines().map(digits).collect();
+    let scanner = Scanner::new(&words);
 
     let start = std::time::Instant::now();
-    let answer: i32 = input.lines().map(digits).sum();
+    let mut answer = 0;
+    let mut failed = false;
+    for (k, line) in input.lines().enumerate() {
+        match digits(&scanner, line) {
+            Ok(d) => answer += d,
+            Err(e) => {
+                eprintln!("line {}: {e}", k + 1);
+                failed = true;
+            }
+        }
+    }
     let duration = start.elapsed();
     println!("got answer in {:?}", duration);
 
-    println!("answer: {answer}")
+    if !failed {
+        println!("answer: {answer}")
+    }
 }
@@ -2,16 +2,17 @@ use once_cell::sync::Lazy;
 use regex::Regex;
 use std::{
     collections::{HashMap, VecDeque},
-    fs,
+    env, fs,
+    io::{self, Write},
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum Pulse {
     H,
     L,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 enum Module {
     FlipFlop {
         on: bool,
@@ -24,10 +25,68 @@ enum Module {
     Broadcast {
         output: Vec<String>,
     },
+    Inverter {
+        output: Vec<String>,
+    },
+    Sink {
+        low: u64,
+        high: u64,
+    },
+}
+
+impl Module {
+    fn output(&self) -> &[String] {
+        match self {
+            Module::FlipFlop { output, .. }
+            | Module::Conjunction { output, .. }
+            | Module::Broadcast { output }
+            | Module::Inverter { output } => output,
+            Module::Sink { .. } => &[],
+        }
+    }
+
+    fn connect(&mut self, input: &str) {
+        if let Module::Conjunction { memory, .. } = self {
+            memory.insert(input.to_string(), Pulse::L);
+        }
+    }
+
+    // returns the pulse to send to every output, if any
+    fn receive(&mut self, from: &str, pulse: Pulse) -> Option<Pulse> {
+        match self {
+            Module::Broadcast { .. } => Some(pulse),
+            Module::FlipFlop { on, .. } => {
+                if pulse == Pulse::H {
+                    return None;
+                }
+                *on = !*on;
+                Some(if *on { Pulse::H } else { Pulse::L })
+            }
+            Module::Conjunction { memory, .. } => {
+                memory.insert(from.to_string(), pulse);
+                if memory.values().all(|p| *p == Pulse::H) {
+                    Some(Pulse::L)
+                } else {
+                    Some(Pulse::H)
+                }
+            }
+            Module::Inverter { .. } => match pulse {
+                Pulse::H => Some(Pulse::L),
+                Pulse::L => Some(Pulse::H),
+            },
+            Module::Sink { low, high } => {
+                match pulse {
+                    Pulse::H => *high += 1,
+                    Pulse::L => *low += 1,
+                }
+                None
+            }
+        }
+    }
 }
 
 fn parse_module(line: &str) -> Option<(String, Module)> {
-    static PATTERN: Lazy<Regex> = Lazy::new(|| Regex::new("^([%&]?)([a-z]+) -> (.*)$").unwrap());
+    static PATTERN: Lazy<Regex> = Lazy::new(|| Regex::new("^([%&!]?)([a-z]+) -> (.*)$").unwrap());
 
     let captures = PATTERN.captures(line)?;
     let type_char = captures.get(1)?.as_str();
@@ -49,54 +108,99 @@ fn parse_module(line: &str) -> Option<(String, Module)> {
                 output,
             },
         )),
+        "!" => Some((name, Module::Inverter { output })),
         _ => None,
     }
 }
 
-fn init_conjunctions(modules: &mut HashMap<String, Module>) -> Option<()> {
-    // find names of conjunction modules
-
-    let conjunctions: Vec<_> = modules
-        .iter()
-        .filter_map(|(n, m)| match m {
-            Module::Conjunction { .. } => Some(n.to_string()),
-            _ => None,
-        })
-        .collect();
+type Network = HashMap<String, Module>;
 
-    // for each name:
-    // 1. collect names of modules that point to it
-    // 2. set the initial state
+fn load_network(input: &str) -> Result<(Network, Vec<String>), Vec<String>> {
+    let mut modules: Network = HashMap::new();
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
 
-    for c_name in conjunctions {
-        let inputs: Vec<_> = modules
-            .iter()
-            .filter_map(|(n, m)| match m {
-                Module::FlipFlop { output, .. }
-                | Module::Conjunction { output, .. }
-                | Module::Broadcast { output } => {
-                    if output.contains(&c_name) {
-                        Some(n.to_string())
-                    } else {
-                        None
-                    }
+    for (k, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse_module(line) {
+            Some((name, m)) => {
+                if modules.insert(name.clone(), m).is_some() {
+                    errors.push(format!("line {}: duplicate module {name}", k + 1));
                 }
-            })
-            .collect();
+            }
+            None => errors.push(format!("line {}: unable to parse {line:?}", k + 1)),
+        }
+    }
+
+    if !matches!(modules.get("broadcaster"), Some(Module::Broadcast { .. })) {
+        errors.push("no broadcaster module".to_string());
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    // outputs with no module of their own just count what they receive
+    let mut edges: Vec<(String, String)> = modules
+        .iter()
+        .flat_map(|(n, m)| m.output().iter().map(|o| (n.to_string(), o.to_string())))
+        .collect();
+    edges.sort();
+    for (from, to) in &edges {
+        if !modules.contains_key(to) {
+            warnings.push(format!(
+                "{from} sends to {to}, which is not defined; adding a sink"
+            ));
+            modules.insert(to.to_string(), Module::Sink { low: 0, high: 0 });
+        }
+        modules.get_mut(to).unwrap().connect(from);
+    }
 
-        if let Module::Conjunction { ref mut memory, .. } = modules.get_mut(&c_name)? {
-            for i in inputs {
-                memory.insert(i, Pulse::L);
+    let mut reached = vec!["broadcaster".to_string()];
+    let mut k = 0;
+    while let Some(name) = reached.get(k) {
+        for o in modules[name].output() {
+            if !reached.contains(o) {
+                reached.push(o.to_string());
             }
         }
+        k += 1;
+    }
+    let mut unreachable: Vec<_> = modules.keys().filter(|n| !reached.contains(n)).collect();
+    unreachable.sort();
+    for name in unreachable {
+        warnings.push(format!("{name} is unreachable from the broadcaster"));
     }
 
-    Some(())
+    Ok((modules, warnings))
 }
 
-fn parse_modules(input: &str) -> HashMap<String, Module> {
-    let modules = input.lines().flat_map(parse_module).collect();
-    modules
+#[test]
+fn test_load_network() {
+    let (modules, warnings) = load_network(TEST_NETWORK).unwrap();
+    assert!(matches!(modules["rx"], Module::Sink { .. }));
+    assert_eq!(
+        warnings,
+        vec!["out sends to rx, which is not defined; adding a sink"]
+    );
+
+    let (mut modules, _) = load_network("broadcaster -> a\n!a -> b\n").unwrap();
+    send_pulse(&mut modules, |_, _, _| {});
+    assert!(matches!(modules["b"], Module::Sink { low: 0, high: 1 }));
+
+    let (_, warnings) = load_network("broadcaster -> a\n%a -> b\n%c -> a\n%b -> a\n").unwrap();
+    assert_eq!(warnings, vec!["c is unreachable from the broadcaster"]);
+
+    let errors = load_network("%a -> b\n%a -> c\n%b -> a\n&c1 -> a\n").unwrap_err();
+    assert_eq!(
+        errors,
+        vec![
+            "line 2: duplicate module a",
+            "line 4: unable to parse \"&c1 -> a\"",
+            "no broadcaster module",
+        ]
+    );
 }
 
 fn receive_pulse(
@@ -107,54 +211,25 @@ fn receive_pulse(
     pulse: Pulse,
 ) {
     if let Some(m) = modules.get_mut(&to) {
-        match m {
-            Module::Broadcast { output } => {
-                for o in output {
-                    pulse_queue.push_back((to.to_string(), o.to_string(), pulse.clone()));
-                }
-            }
-            Module::FlipFlop { ref mut on, output } => {
-                if let Pulse::L = pulse {
-                    *on = !(*on);
-                    for o in output {
-                        pulse_queue.push_back((
-                            to.to_string(),
-                            o.to_string(),
-                            if *on { Pulse::H } else { Pulse::L },
-                        ))
-                    }
-                }
-            }
-            Module::Conjunction {
-                ref mut memory,
-                output,
-            } => {
-                memory.insert(from, pulse);
-                let pulse_to_send = if memory.iter().all(|(_, p)| match p {
-                    Pulse::H => true,
-                    Pulse::L => false,
-                }) {
-                    Pulse::L
-                } else {
-                    Pulse::H
-                };
-                for o in output {
-                    pulse_queue.push_back((to.to_string(), o.to_string(), pulse_to_send.clone()));
-                }
+        if let Some(pulse_to_send) = m.receive(&from, pulse) {
+            for o in m.output() {
+                pulse_queue.push_back((to.to_string(), o.to_string(), pulse_to_send));
             }
         }
     }
 }
 
-fn send_pulse(modules: &mut HashMap<String, Module>) -> (u32, u32) {
+fn send_pulse<F: FnMut(&str, &str, Pulse)>(
+    modules: &mut HashMap<String, Module>,
+    mut observe: F,
+) -> (u32, u32) {
     let mut h_sent = 0;
     let mut l_sent = 0;
     let mut pulse_queue: VecDeque<(String, String, Pulse)> = VecDeque::new();
     pulse_queue.push_back(("button".to_string(), "broadcaster".to_string(), Pulse::L));
 
-    // println!("");
     while let Some((from, to, pulse)) = pulse_queue.pop_front() {
-        // println!("{from} -{:?}-> {to}", pulse);
+        observe(&from, &to, pulse);
 
         match pulse {
             Pulse::H => h_sent += 1,
@@ -167,64 +242,720 @@ fn send_pulse(modules: &mut HashMap<String, Module>) -> (u32, u32) {
     (h_sent, l_sent)
 }
 
-fn print_state(i: usize, modules: &HashMap<String, Module>) {
-    for (name, m) in modules {
-        // if ["bt", "dl", "fr", "rv"].contains(&name.as_str()) {
-        // if ["mj", "qs", "rd", "cs"].contains(&name.as_str()) {
-        if ["cs"].contains(&name.as_str()) {
-            if let Module::Conjunction { memory, .. } = m {
-                // let mut state: Vec<_> = memory.iter().collect();
-                // state.sort_by_key(|m| m.0);
-                // println!("{i} {:?}", state);
-
-                // let mut state: Vec<_> = memory.iter().collect();
-                // state.sort_by_key(|m| m.0);
-                // if let (_, Pulse::H) = state[5] {
-                //     println!("{i} {:?}  ", state);
-                // }
-
-                if memory.iter().all(|(_, p)| match p {
-                    Pulse::H => true,
-                    _ => false,
-                }) {
-                    println!("{i} {name} {:?}  ", memory);
+fn find_feeder<'a>(modules: &'a HashMap<String, Module>, target: &str) -> Result<&'a str, String> {
+    let feeders: Vec<_> = modules
+        .iter()
+        .filter(|(_, m)| m.output().iter().any(|o| o == target))
+        .collect();
+
+    match feeders[..] {
+        [(name, Module::Conjunction { .. })] => Ok(name),
+        [(name, _)] => Err(format!(
+            "{target} is fed by {name}, which is not a conjunction"
+        )),
+        [] => Err(format!("no module sends pulses to {target}")),
+        _ => Err(format!(
+            "{target} has {} inputs, expected one",
+            feeders.len()
+        )),
+    }
+}
+
+fn find_periods(
+    modules: &HashMap<String, Module>,
+    feeder: &str,
+    max_presses: u64,
+) -> Result<Vec<(String, u64)>, String> {
+    let mut modules = modules.clone();
+    let inputs: Vec<String> = match modules.get(feeder) {
+        Some(Module::Conjunction { memory, .. }) => memory.keys().cloned().collect(),
+        _ => return Err(format!("{feeder} is not a conjunction")),
+    };
+
+    // presses on which each input sent a high pulse to the feeder
+    let mut fired: HashMap<String, Vec<u64>> = HashMap::new();
+
+    for press in 1..=max_presses {
+        send_pulse(&mut modules, |from, to, pulse| {
+            if to == feeder && pulse == Pulse::H {
+                fired.entry(from.to_string()).or_default().push(press);
+            }
+        });
+
+        // the feeder only sees a cycle if every high pulse is reset within the same press
+        if let Some(Module::Conjunction { memory, .. }) = modules.get(feeder) {
+            if let Some((name, _)) = memory.iter().find(|(_, p)| **p == Pulse::H) {
+                return Err(format!(
+                    "{name} is still high into {feeder} after press {press}"
+                ));
+            }
+        }
+
+        if inputs
+            .iter()
+            .all(|i| fired.get(i).is_some_and(|p| p.len() >= 2))
+        {
+            break;
+        }
+    }
+
+    inputs
+        .into_iter()
+        .map(|i| {
+            let presses = fired.get(&i).map(|p| &p[..]).unwrap_or_default();
+            match presses {
+                [first, second, ..] if *first == *second - *first => Ok((i, *first)),
+                [first, second, ..] => Err(format!(
+                    "{i} fires on presses {first} and {second}, which is not a cycle from press 0"
+                )),
+                _ => Err(format!(
+                    "{i} did not fire twice within {max_presses} presses"
+                )),
+            }
+        })
+        .collect()
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn lcm(a: u64, b: u64) -> u64 {
+    a / gcd(a, b) * b
+}
+
+fn presses_until_low(modules: &HashMap<String, Module>, target: &str) -> Result<u64, String> {
+    let feeder = find_feeder(modules, target)?;
+    let periods = find_periods(modules, feeder, 100_000)?;
+    for (name, period) in &periods {
+        println!("{name} -> {feeder}: every {period} presses");
+    }
+    Ok(periods.iter().fold(1, |acc, (_, p)| lcm(acc, *p)))
+}
+
+// two counters from the broadcaster, firing every 3 and 5 presses
+#[cfg(test)]
+static TEST_NETWORK: &str = "broadcaster -> xa, ya
+%xa -> xb, cx
+%xb -> cx
+&cx -> xa, ix
+&ix -> out
+%ya -> yb, cy
+%yb -> yc
+%yc -> cy
+&cy -> yb, ya, iy
+&iy -> out
+&out -> rx
+";
+
+#[test]
+fn test_presses_until_low() {
+    let (modules, _) = load_network(TEST_NETWORK).unwrap();
+
+    let periods = find_periods(&modules, "out", 100).unwrap();
+    let periods: HashMap<_, _> = periods.into_iter().collect();
+    assert_eq!(periods["ix"], 3);
+    assert_eq!(periods["iy"], 5);
+    assert_eq!(presses_until_low(&modules, "rx"), Ok(15));
+
+    assert!(presses_until_low(&modules, "missing").is_err());
+}
+
+#[derive(Debug)]
+struct Counter {
+    conjunction: String,
+    bits: Vec<String>,
+    taps: Vec<bool>,
+    period: u64,
+}
+
+fn find_counter(modules: &HashMap<String, Module>, first: &str) -> Result<Counter, String> {
+    let mut bits: Vec<String> = Vec::new();
+    let mut taps: Vec<bool> = Vec::new();
+    let mut conjunction: Option<String> = None;
+    let mut next = Some(first.to_string());
+
+    // each bit is a flip-flop that carries into the next one and may tap the conjunction
+    while let Some(name) = next {
+        let Some(Module::FlipFlop { output, .. }) = modules.get(&name) else {
+            return Err(format!(
+                "{name} in the chain from {first} is not a flip-flop"
+            ));
+        };
+
+        next = None;
+        let mut tap = false;
+        for o in output {
+            match modules.get(o) {
+                Some(Module::FlipFlop { .. }) if next.is_none() => next = Some(o.to_string()),
+                Some(Module::Conjunction { .. }) if !tap => {
+                    if conjunction.get_or_insert_with(|| o.to_string()) != o {
+                        return Err(format!(
+                            "{name} taps {o}, but the chain already taps another conjunction"
+                        ));
+                    }
+                    tap = true;
                 }
+                _ => return Err(format!("{name} has an unexpected output {o}")),
             }
         }
+
+        if bits.contains(&name) {
+            return Err(format!("the chain from {first} loops back to {name}"));
+        }
+        bits.push(name);
+        taps.push(tap);
     }
-    // print!("\n");
+
+    let conjunction =
+        conjunction.ok_or(format!("the chain from {first} never taps a conjunction"))?;
+
+    // the conjunction resets the counter by pulsing every bit that isn't a tap, plus bit 0
+    let resets = modules[&conjunction].output();
+    for (k, (bit, tap)) in bits.iter().zip(&taps).enumerate() {
+        if (k == 0 || !tap) != resets.contains(bit) {
+            return Err(format!(
+                "{conjunction} does not reset {bit} the way a counter would"
+            ));
+        }
+    }
+
+    let period = taps
+        .iter()
+        .enumerate()
+        .filter(|(_, tap)| **tap)
+        .map(|(k, _)| 1 << k)
+        .sum();
+
+    Ok(Counter {
+        conjunction,
+        bits,
+        taps,
+        period,
+    })
 }
 
-fn main() {
-    let input = fs::read_to_string("input").expect("unable to read input");
-    let mut modules = parse_modules(&input);
-    init_conjunctions(&mut modules).expect("unable to initialize conjunctions");
+fn find_counters(modules: &HashMap<String, Module>) -> Result<Vec<Counter>, String> {
+    let Some(Module::Broadcast { output }) = modules.get("broadcaster") else {
+        return Err("no broadcaster".to_string());
+    };
+    output.iter().map(|o| find_counter(modules, o)).collect()
+}
+
+#[test]
+fn test_find_counters() {
+    let (modules, _) = load_network(TEST_NETWORK).unwrap();
 
-    // println!("");
-    // for m in &modules {
-    //     eprintln!("{:?}", m);
-    // }
+    let counters = find_counters(&modules).unwrap();
+    assert_eq!(counters.len(), 2);
+    assert_eq!(counters[0].conjunction, "cx");
+    assert_eq!(counters[0].bits, vec!["xa", "xb"]);
+    assert_eq!(counters[0].period, 3);
+    assert_eq!(counters[1].conjunction, "cy");
+    assert_eq!(counters[1].taps, vec![true, false, true]);
+    assert_eq!(counters[1].period, 5);
+}
 
-    let mut h_sent: u32 = 0;
-    let mut l_sent: u32 = 0;
-    let mut i = 0;
+#[derive(Debug)]
+struct Delivery {
+    press: u64,
+    seq: u64,
+    from: String,
+    to: String,
+    pulse: Pulse,
+}
+
+fn trace_presses(modules: &mut HashMap<String, Module>, presses: u64) -> Vec<Delivery> {
+    let mut trace = Vec::new();
+    for press in 1..=presses {
+        let mut seq = 0;
+        send_pulse(modules, |from, to, pulse| {
+            trace.push(Delivery {
+                press,
+                seq,
+                from: from.to_string(),
+                to: to.to_string(),
+                pulse,
+            });
+            seq += 1;
+        });
+    }
+    trace
+}
+
+fn vcd_id(mut k: usize) -> String {
+    // identifiers are drawn from the 94 printable ASCII characters
+    let mut id = String::new();
     loop {
-        if i % 500_000 == 0 {
-            println!("{i}");
+        id.push((b'!' + (k % 94) as u8) as char);
+        k /= 94;
+        if k == 0 {
+            return id;
+        }
+    }
+}
+
+fn write_vcd(trace: &[Delivery], modules: &HashMap<String, Module>) -> String {
+    let mut names: Vec<&str> = modules.keys().map(|n| n.as_str()).collect();
+    names.push("button");
+    names.sort();
+    let ids: HashMap<&str, String> = names
+        .iter()
+        .enumerate()
+        .map(|(k, n)| (*n, vcd_id(k + 1)))
+        .collect();
+    let press_id = vcd_id(0);
+
+    // each press gets its own block of timestamps, wide enough for its longest cascade
+    let max_seq = trace.iter().map(|d| d.seq).max().unwrap_or(0);
+    let stride = 10u64.pow((max_seq + 1).ilog10() + 1);
+
+    let mut vcd = String::new();
+    vcd += "$timescale 1ns $end\n$scope module network $end\n";
+    vcd += &format!("$var integer 32 {press_id} press $end\n");
+    for n in &names {
+        vcd += &format!("$var wire 1 {} {n} $end\n", ids[n]);
+    }
+    vcd += "$upscope $end\n$enddefinitions $end\n";
+
+    vcd += "#0\n$dumpvars\nb0 ";
+    vcd += &press_id;
+    vcd += "\n";
+    for n in &names {
+        vcd += &format!("0{}\n", ids[n]);
+    }
+    vcd += "$end\n";
+
+    let mut levels: HashMap<&str, Pulse> = HashMap::new();
+    let mut press = 0;
+    for d in trace {
+        let time = d.press * stride + d.seq;
+        let changed = levels.get(d.from.as_str()).unwrap_or(&Pulse::L) != &d.pulse;
+        if d.press != press || changed {
+            vcd += &format!("#{time}\n");
+        }
+        if d.press != press {
+            vcd += &format!("b{:b} {press_id}\n", d.press);
+            press = d.press;
+        }
+        if changed {
+            let value = match d.pulse {
+                Pulse::H => '1',
+                Pulse::L => '0',
+            };
+            vcd += &format!("{value}{}\n", ids[d.from.as_str()]);
+            levels.insert(&d.from, d.pulse);
+        }
+    }
+
+    vcd
+}
+
+#[test]
+fn test_write_vcd() {
+    let (mut modules, _) = load_network(TEST_NETWORK).unwrap();
+
+    let trace = trace_presses(&mut modules, 3);
+    assert_eq!(trace[0].from, "button");
+    assert_eq!(trace[0].to, "broadcaster");
+    assert_eq!((trace[0].press, trace[0].seq), (1, 0));
+    assert!(trace
+        .iter()
+        .any(|d| d.press == 3 && d.from == "ix" && d.pulse == Pulse::H));
+
+    let vcd = write_vcd(&trace, &modules);
+    let names = ["button", "broadcaster", "xa", "cx", "ix", "out", "yc"];
+    for n in names {
+        assert!(vcd.contains(&format!(" {n} $end\n")));
+    }
+    // xa turns on during the first press; the rx sink never emits anything
+    assert!(vcd.contains("#100\nb1 !\n"));
+    assert!(vcd.contains(" rx $end"));
+}
+
+struct Debugger {
+    modules: HashMap<String, Module>,
+    queue: VecDeque<(String, String, Pulse)>,
+    presses: u64,
+    seq: u64,
+    breakpoints: Vec<(String, Pulse)>,
+    watches: Vec<String>,
+}
+
+impl Debugger {
+    fn new(modules: HashMap<String, Module>) -> Self {
+        Debugger {
+            modules,
+            queue: VecDeque::new(),
+            presses: 0,
+            seq: 0,
+            breakpoints: Vec::new(),
+            watches: Vec::new(),
+        }
+    }
+
+    // delivers one pulse, pressing the button first if nothing is in flight
+    fn step(&mut self) -> (Delivery, bool) {
+        if self.queue.is_empty() {
+            self.presses += 1;
+            self.seq = 0;
+            self.queue
+                .push_back(("button".to_string(), "broadcaster".to_string(), Pulse::L));
+        }
+
+        let (from, to, pulse) = self.queue.pop_front().unwrap();
+        let sent = self.queue.len();
+        receive_pulse(
+            &mut self.queue,
+            &mut self.modules,
+            from.clone(),
+            to.clone(),
+            pulse,
+        );
+        let hit = self
+            .queue
+            .iter()
+            .skip(sent)
+            .any(|(f, _, p)| self.breakpoints.contains(&(f.to_string(), *p)));
+
+        let delivery = Delivery {
+            press: self.presses,
+            seq: self.seq,
+            from,
+            to,
+            pulse,
+        };
+        self.seq += 1;
+        (delivery, hit)
+    }
+
+    // finishes any press in flight, then presses the button `n` more times,
+    // stopping early at a breakpoint
+    fn press(&mut self, n: u64) -> Option<Delivery> {
+        let target = self.presses + n;
+        while self.presses < target || !self.queue.is_empty() {
+            let (delivery, hit) = self.step();
+            if hit {
+                return Some(delivery);
+            }
         }
+        None
+    }
 
-        let (h, l) = send_pulse(&mut modules);
+    fn dump(&self) -> String {
+        let mut lines = vec![format!("presses {} {}", self.presses, self.seq)];
 
-        print_state(i, &modules);
+        let mut names: Vec<_> = self.modules.keys().collect();
+        names.sort();
+        for name in names {
+            match &self.modules[name] {
+                Module::FlipFlop { on, .. } => lines.push(format!("flipflop {name} {}", *on as u8)),
+                Module::Conjunction { memory, .. } => {
+                    let mut inputs: Vec<_> = memory.iter().collect();
+                    inputs.sort_by_key(|(i, _)| *i);
+                    for (i, p) in inputs {
+                        lines.push(format!("memory {name} {i} {p:?}"));
+                    }
+                }
+                Module::Sink { low, high } => lines.push(format!("sink {name} {low} {high}")),
+                Module::Broadcast { .. } | Module::Inverter { .. } => {}
+            }
+        }
 
-        // println!("\nlow: {l}\nhigh: {h}");
-        h_sent += h;
-        l_sent += l;
+        for (from, to, pulse) in &self.queue {
+            lines.push(format!("queue {from} {to} {pulse:?}"));
+        }
 
-        i += 1;
+        lines.join("\n") + "\n"
     }
 
-    println!("\ntotal low: {l_sent}\ntotal high: {h_sent}\n");
-    let answer = h_sent * l_sent;
-    println!("answer: {answer}");
+    fn restore(&mut self, state: &str) -> Result<(), String> {
+        let pulse = |p: &str| match p {
+            "H" => Ok(Pulse::H),
+            "L" => Ok(Pulse::L),
+            _ => Err(format!("unknown pulse {p}")),
+        };
+        let number = |n: &str| n.parse::<u64>().map_err(|e| format!("{n}: {e}"));
+
+        // nothing changes unless the whole file parses
+        let mut modules = self.modules.clone();
+        let (mut presses, mut seq) = (self.presses, self.seq);
+        let mut queue = VecDeque::new();
+        for line in state.lines() {
+            let words: Vec<_> = line.split_whitespace().collect();
+            let module = |name: &str| {
+                modules
+                    .get(name)
+                    .cloned()
+                    .ok_or(format!("unknown module {name}"))
+            };
+            let updated = match words[..] {
+                ["presses", p, s] => {
+                    presses = number(p)?;
+                    seq = number(s)?;
+                    None
+                }
+                ["flipflop", name, on] => match module(name)? {
+                    Module::FlipFlop { output, .. } => Some((
+                        name,
+                        Module::FlipFlop {
+                            on: match on {
+                                "0" => false,
+                                "1" => true,
+                                _ => {
+                                    return Err(format!("flip-flop state must be 0 or 1, got {on}"))
+                                }
+                            },
+                            output,
+                        },
+                    )),
+                    _ => return Err(format!("{name} is not a flip-flop")),
+                },
+                ["memory", name, input, p] => match module(name)? {
+                    Module::Conjunction { mut memory, output } => {
+                        memory.insert(input.to_string(), pulse(p)?);
+                        Some((name, Module::Conjunction { memory, output }))
+                    }
+                    _ => return Err(format!("{name} is not a conjunction")),
+                },
+                ["sink", name, low, high] => match module(name)? {
+                    Module::Sink { .. } => Some((
+                        name,
+                        Module::Sink {
+                            low: number(low)?,
+                            high: number(high)?,
+                        },
+                    )),
+                    _ => return Err(format!("{name} is not a sink")),
+                },
+                ["queue", from, to, p] => {
+                    queue.push_back((from.to_string(), to.to_string(), pulse(p)?));
+                    None
+                }
+                _ => return Err(format!("unable to parse {line:?}")),
+            };
+            if let Some((name, m)) = updated {
+                modules.insert(name.to_string(), m);
+            }
+        }
+        self.modules = modules;
+        self.presses = presses;
+        self.seq = seq;
+        self.queue = queue;
+
+        Ok(())
+    }
+
+    fn print_watches(&self) {
+        for name in &self.watches {
+            if let Some(Module::Conjunction { memory, .. }) = self.modules.get(name) {
+                let mut inputs: Vec<_> = memory.iter().collect();
+                inputs.sort_by_key(|(i, _)| *i);
+                let state: Vec<_> = inputs.iter().map(|(i, p)| format!("{i}={p:?}")).collect();
+                println!("  {name}: {}", state.join(" "));
+            }
+        }
+    }
+
+    fn command(&mut self, line: &str) -> Result<(), String> {
+        let words: Vec<_> = line.split_whitespace().collect();
+        let pulse = |p: &str| match p {
+            "high" => Ok(Pulse::H),
+            "low" => Ok(Pulse::L),
+            _ => Err(format!("expected high or low, got {p}")),
+        };
+
+        match words[..] {
+            ["press"] | ["press", _] => {
+                let n = words.get(1).map_or(Ok(1), |n| n.parse().map_err(|_| format!("not a number: {n}")))?;
+                match self.press(n) {
+                    Some(d) => println!("break at {}.{}: {} -{:?}-> {}", d.press, d.seq, d.from, d.pulse, d.to),
+                    None => println!("{} presses", self.presses),
+                }
+            }
+            ["step"] => {
+                let (d, _) = self.step();
+                println!("{}.{} {} -{:?}-> {}", d.press, d.seq, d.from, d.pulse, d.to);
+            }
+            ["break", name, p] => {
+                if !self.modules.contains_key(name) {
+                    return Err(format!("unknown module {name}"));
+                }
+                self.breakpoints.push((name.to_string(), pulse(p)?));
+            }
+            ["clear"] => self.breakpoints.clear(),
+            ["watch", name] => match self.modules.get(name) {
+                Some(Module::Conjunction { .. }) => self.watches.push(name.to_string()),
+                _ => return Err(format!("{name} is not a conjunction")),
+            },
+            ["dump", file] => fs::write(file, self.dump()).map_err(|e| format!("{file}: {e}"))?,
+            ["restore", file] => {
+                let state = fs::read_to_string(file).map_err(|e| format!("{file}: {e}"))?;
+                self.restore(&state)?;
+            }
+            ["help"] => println!(
+                "press [n], step, break <module> <high|low>, clear, watch <conjunction>, dump <file>, restore <file>, quit"
+            ),
+            [] => {}
+            _ => return Err(format!("unknown command {:?}", line.trim())),
+        }
+
+        self.print_watches();
+        Ok(())
+    }
+}
+
+#[test]
+fn test_debugger() {
+    let (modules, _) = load_network(TEST_NETWORK).unwrap();
+    let mut debugger = Debugger::new(modules.clone());
+
+    // cx emits a low pulse once its counter reaches 3
+    debugger.command("break cx low").unwrap();
+    let d = debugger.press(10).unwrap();
+    assert_eq!((d.press, d.to.as_str()), (3, "cx"));
+    assert!(!debugger.queue.is_empty());
+
+    let state = debugger.dump();
+    assert!(state.starts_with("presses 3 "));
+    assert!(state.contains("queue cx xa L\nqueue cx ix L\n"));
+
+    let mut restored = Debugger::new(modules);
+    restored.restore(&state).unwrap();
+    assert_eq!(restored.dump(), state);
+
+    // both finish the third press, press four more times and carry on identically
+    debugger.command("clear").unwrap();
+    debugger.press(4);
+    restored.press(4);
+    assert_eq!(debugger.presses, 7);
+    assert_eq!(restored.dump(), debugger.dump());
+
+    // pressing 0 times only finishes the press in flight
+    restored.restore(&state).unwrap();
+    restored.press(0);
+    assert_eq!((restored.presses, restored.queue.len()), (3, 0));
+
+    // a bad line leaves the debugger exactly as it was
+    let before = restored.dump();
+    assert!(restored.restore("flipflop cx 1").is_err());
+    let flipflop = state
+        .lines()
+        .find(|l| l.starts_with("flipflop"))
+        .unwrap()
+        .rsplit_once(' ')
+        .unwrap()
+        .0
+        .to_string();
+    assert!(restored
+        .restore(&format!(
+            "presses 9 0
+{flipflop} 2
+"
+        ))
+        .is_err());
+    assert!(restored
+        .restore(&format!(
+            "presses 9 0
+queue a b L
+{flipflop} 1
+bogus
+"
+        ))
+        .is_err());
+    assert_eq!(restored.dump(), before);
+}
+
+fn debug(modules: HashMap<String, Module>) {
+    let mut debugger = Debugger::new(modules);
+    let mut line = String::new();
+    loop {
+        print!("> ");
+        io::stdout().flush().unwrap();
+
+        line.clear();
+        if io::stdin().read_line(&mut line).unwrap_or(0) == 0 || line.trim() == "quit" {
+            break;
+        }
+        if let Err(e) = debugger.command(&line) {
+            println!("error: {e}");
+        }
+    }
+}
+
+fn main() {
+    let input = fs::read_to_string("input").expect("unable to read input");
+    let (modules, warnings) = match load_network(&input) {
+        Ok(network) => network,
+        Err(errors) => {
+            for e in errors {
+                eprintln!("error: {e}");
+            }
+            return;
+        }
+    };
+    for w in warnings {
+        eprintln!("warning: {w}");
+    }
+
+    let args: Vec<String> = env::args().collect();
+    let presses_for = |flag: &str| {
+        let k = args.iter().position(|a| a == flag)?;
+        let presses = args.get(k + 1).and_then(|n| n.parse().ok());
+        Some(presses.unwrap_or_else(|| panic!("{flag} takes a number of presses")))
+    };
+
+    if args.iter().any(|a| a == "--debug") {
+        debug(modules);
+        return;
+    }
+
+    if let Some(presses) = presses_for("--trace") {
+        for d in trace_presses(&mut modules.clone(), presses) {
+            println!("{}.{} {} -{:?}-> {}", d.press, d.seq, d.from, d.pulse, d.to);
+        }
+        return;
+    }
+
+    if let Some(presses) = presses_for("--vcd") {
+        let trace = trace_presses(&mut modules.clone(), presses);
+        fs::write("trace.vcd", write_vcd(&trace, &modules)).expect("unable to write trace.vcd");
+        println!("wrote {} deliveries to trace.vcd", trace.len());
+        return;
+    }
+
+    if args.iter().any(|a| a == "--counters") {
+        let counters = find_counters(&modules).expect("unable to find counters");
+        for c in &counters {
+            let pattern: String = c
+                .taps
+                .iter()
+                .rev()
+                .map(|t| if *t { '1' } else { '0' })
+                .collect();
+            let taps: Vec<_> = c
+                .bits
+                .iter()
+                .zip(&c.taps)
+                .filter(|(_, t)| **t)
+                .map(|(b, _)| b)
+                .collect();
+            println!(
+                "{}: {} = {} (taps {:?})",
+                c.conjunction, pattern, c.period, taps
+            );
+        }
+        let answer = counters.iter().fold(1, |acc, c| lcm(acc, c.period));
+        println!("answer: {answer}");
+        return;
+    }
+
+    match presses_until_low(&modules, "rx") {
+        Ok(answer) => println!("answer: {answer}"),
+        Err(e) => eprintln!("unable to find a cycle: {e}"),
+    }
 }
@@ -1,7 +1,4 @@
-use std::{
-    collections::{BTreeSet, HashSet},
-    fs,
-};
+use std::{collections::VecDeque, env, fs};
 
 fn parse_input(input: &str) -> Vec<Vec<bool>> {
     input
@@ -30,49 +27,100 @@ fn find_start(input: &str) -> Option<(usize, usize)> {
     None
 }
 
-fn count_reachable_points(map: &Vec<Vec<bool>>, start: (usize, usize), steps: usize) -> usize {
-    let mut count_reachable = 0;
-    let mut visited: HashSet<(usize, usize)> = HashSet::new();
-    let mut unvisited: BTreeSet<(usize, (usize, usize))> = BTreeSet::new();
-    unvisited.insert((steps, start));
+fn distance_field(map: &[Vec<bool>], start: (usize, usize)) -> Vec<Vec<Option<usize>>> {
+    let mut distances: Vec<Vec<Option<usize>>> =
+        map.iter().map(|row| vec![None; row.len()]).collect();
+    let mut queue: VecDeque<(usize, usize)> = VecDeque::new();
+    distances[start.0][start.1] = Some(0);
+    queue.push_back(start);
 
-    while let Some((s, (i, j))) = unvisited.pop_last() {
-        visited.insert((i, j));
-
-        if s % 2 == 0 {
-            count_reachable += 1;
-        }
-
-        if s == 0 {
-            continue;
-        }
+    while let Some((i, j)) = queue.pop_front() {
+        let d = distances[i][j].unwrap();
 
         let up = i.checked_sub(1).map(|k| (k, j));
         let down = Some((i + 1, j));
         let right = Some((i, j + 1));
         let left = j.checked_sub(1).map(|l| (i, l));
 
-        for (k, l) in [up, down, left, right].iter().flatten() {
-            if let Some(row) = map.get(*k) {
-                if let Some(tile) = row.get(*l) {
-                    if *tile {
-                        if !visited.contains(&(*k, *l)) {
-                            unvisited.insert((s - 1, (*k, *l)));
-                        }
-                    }
-                }
+        for (k, l) in [up, down, left, right].into_iter().flatten() {
+            if map.get(k).and_then(|row| row.get(l)) == Some(&true) && distances[k][l].is_none() {
+                distances[k][l] = Some(d + 1);
+                queue.push_back((k, l));
             }
         }
     }
 
-    count_reachable
+    distances
+}
+
+// reachable[d] counts the plots at most d steps away with the same parity as d
+struct Reachable {
+    by_parity: Vec<usize>,
+}
+
+impl Reachable {
+    fn new(distances: &[Vec<Option<usize>>]) -> Self {
+        let mut by_parity: Vec<usize> = vec![0; 2];
+        for d in distances.iter().flatten().flatten() {
+            if by_parity.len() <= *d {
+                by_parity.resize(d + 1, 0);
+            }
+            by_parity[*d] += 1;
+        }
+        for d in 2..by_parity.len() {
+            by_parity[d] += by_parity[d - 2];
+        }
+        Reachable { by_parity }
+    }
+
+    fn count(&self, steps: usize) -> usize {
+        let last = self.by_parity.len() - 1;
+        // past the farthest plot, only parity matters
+        let d = if steps <= last {
+            steps
+        } else {
+            last - (last + steps) % 2
+        };
+        self.by_parity[d]
+    }
+}
+
+#[test]
+fn test_reachable() {
+    let input = "...........
+.....###.#.
+.###.##..#.
+..#.#...#..
+....#.#....
+.##..S####.
+.##..#...#.
+.......##..
+.##.#.####.
+.##..##.##.
+...........
+";
+    let map = parse_input(input);
+    let start = find_start(input).unwrap();
+    let reachable = Reachable::new(&distance_field(&map, start));
+
+    assert_eq!(reachable.count(0), 1);
+    assert_eq!(reachable.count(1), 2);
+    assert_eq!(reachable.count(2), 4);
+    assert_eq!(reachable.count(3), 6);
+    assert_eq!(reachable.count(6), 16);
+    assert_eq!(reachable.count(1000), 42);
+    assert_eq!(reachable.count(1001), 39);
 }
 
 fn main() {
     let input = fs::read_to_string("input").expect("unable to read input");
     let map = parse_input(&input);
     let start = find_start(&input).expect("unable to find start");
+    let steps = env::args()
+        .nth(1)
+        .map_or(64, |a| a.parse().expect("steps must be a number"));
 
-    let answer = count_reachable_points(&map, start, 64);
+    let reachable = Reachable::new(&distance_field(&map, start));
+    let answer = reachable.count(steps);
     println!("answer: {answer}");
 }
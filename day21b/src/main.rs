@@ -1,7 +1,6 @@
 use std::{
-    collections::{BTreeSet, HashMap, HashSet},
-    fs,
-    sync::{Mutex, OnceLock},
+    collections::{HashMap, VecDeque},
+    env, fs,
 };
 
 fn parse_input(input: &str) -> Vec<Vec<bool>> {
@@ -19,194 +18,483 @@ fn parse_input(input: &str) -> Vec<Vec<bool>> {
         .collect()
 }
 
-fn count_reachable(
-    map: &Vec<Vec<bool>>,
-    start: (usize, usize),
-    even: bool,
-    limit: Option<usize>,
-) -> usize {
-    let mut reachable = 0;
-    let mut visited: HashSet<(usize, usize)> = HashSet::new();
-    let mut unvisited: BTreeSet<(usize, (usize, usize))> = BTreeSet::new();
-    unvisited.insert((0, start));
-
-    while let Some((s, (i, j))) = unvisited.pop_first() {
-        if visited.contains(&(i, j)) {
-            continue;
+fn find_start(input: &str) -> Option<(usize, usize)> {
+    for (i, line) in input.lines().enumerate() {
+        for (j, c) in line.chars().enumerate() {
+            if c == 'S' {
+                return Some((i, j));
+            }
         }
+    }
 
-        visited.insert((i, j));
+    None
+}
 
-        if even {
-            if s % 2 == 0 {
-                reachable += 1;
-            }
-        } else {
-            if s % 2 == 1 {
-                reachable += 1;
-            }
-        }
+fn distance_field(map: &[Vec<bool>], start: (usize, usize)) -> Vec<Vec<Option<usize>>> {
+    let mut distances: Vec<Vec<Option<usize>>> =
+        map.iter().map(|row| vec![None; row.len()]).collect();
+    let mut queue: VecDeque<(usize, usize)> = VecDeque::new();
+    distances[start.0][start.1] = Some(0);
+    queue.push_back(start);
 
-        if let Some(l) = limit {
-            if s == l {
-                continue;
-            }
-        }
+    while let Some((i, j)) = queue.pop_front() {
+        let d = distances[i][j].unwrap();
 
         let up = i.checked_sub(1).map(|k| (k, j));
         let down = Some((i + 1, j));
         let right = Some((i, j + 1));
         let left = j.checked_sub(1).map(|l| (i, l));
 
-        for (k, l) in [up, down, left, right].iter().flatten() {
-            if let Some(row) = map.get(*k) {
-                if let Some(tile) = row.get(*l) {
-                    if *tile {
-                        if !visited.contains(&(*k, *l)) {
-                            unvisited.insert((s + 1, (*k, *l)));
+        for (k, l) in [up, down, left, right].into_iter().flatten() {
+            if map.get(k).and_then(|row| row.get(l)) == Some(&true) && distances[k][l].is_none() {
+                distances[k][l] = Some(d + 1);
+                queue.push_back((k, l));
+            }
+        }
+    }
+
+    distances
+}
+
+fn tile_map(map: &[Vec<bool>], copies: usize) -> Vec<Vec<bool>> {
+    (0..copies)
+        .flat_map(|_| map.iter())
+        .map(|row| (0..copies).flat_map(|_| row.iter().copied()).collect())
+        .collect()
+}
+
+// number of n >= 0 with x + n * step <= limit and the same parity as limit
+fn count_progression(x: usize, step: usize, limit: usize) -> usize {
+    if x > limit {
+        return 0;
+    }
+    let n = (limit - x) / step;
+    match (step % 2, (limit - x) % 2) {
+        (0, 0) => n + 1,
+        (0, _) => 0,
+        (_, 0) => n / 2 + 1,
+        _ => n.div_ceil(2),
+    }
+}
+
+// Once the BFS frontier has settled, distances to a tile one copy further
+// out in a given direction grow by the same amount for every cell, so a
+// (2k + 1)-square block of tiles is enough to extrapolate every other tile.
+// That amount is the map height or width when the map has an open row or
+// column, and more when the walk has to weave around rocks.
+struct Block {
+    shape: (usize, usize),
+    radius: usize,
+    distances: Vec<Vec<Option<usize>>>,
+}
+
+// per-tile distance increments going up, down, left and right
+type Increments = [usize; 4];
+
+impl Block {
+    fn new(map: &[Vec<bool>], start: (usize, usize), radius: usize) -> Self {
+        let shape = (map.len(), map[0].len());
+        // an extra ring of tiles keeps the outer ring's distances from being
+        // cut short by walks that would leave the block and come back
+        let copies = 2 * radius + 3;
+        let center = (
+            start.0 + (radius + 1) * shape.0,
+            start.1 + (radius + 1) * shape.1,
+        );
+        let distances = distance_field(&tile_map(map, copies), center);
+        Block {
+            shape,
+            radius,
+            distances,
+        }
+    }
+
+    fn get(&self, tile: (i64, i64), cell: (usize, usize)) -> Option<usize> {
+        let r = self.radius as i64 + 1;
+        let i = (tile.0 + r) as usize * self.shape.0 + cell.0;
+        let j = (tile.1 + r) as usize * self.shape.1 + cell.1;
+        self.distances[i][j]
+    }
+
+    // the closest any walk gets to leaving the block, if one does at all
+    fn edge_distance(&self) -> Option<usize> {
+        let last_row = self.distances.len() - 1;
+        let last_col = self.distances[0].len() - 1;
+        self.distances
+            .iter()
+            .enumerate()
+            .flat_map(|(i, row)| row.iter().enumerate().map(move |(j, d)| (i, j, d)))
+            .filter(|(i, j, _)| *i == 0 || *j == 0 || *i == last_row || *j == last_col)
+            .filter_map(|(_, _, d)| *d)
+            .min()
+    }
+
+    // every walk of at most `limit` steps stays inside the block, so the
+    // block's own distances are exact for it
+    fn contains(&self, limit: usize) -> bool {
+        self.edge_distance().is_none_or(|d| limit <= d)
+    }
+
+    fn count_inside(&self, limit: usize) -> usize {
+        self.distances
+            .iter()
+            .flatten()
+            .flatten()
+            .filter(|d| **d <= limit && **d % 2 == limit % 2)
+            .count()
+    }
+
+    // checks that stepping out through the last two rings adds the same
+    // distance to every cell on each side of the block, and returns it
+    fn increments(&self) -> Option<Increments> {
+        let r = self.radius as i64;
+        let (h, w) = self.shape;
+        let sides = [((-1, 0), h), ((1, 0), h), ((0, -1), w), ((0, 1), w)];
+        // tile `k` rings out, `t` along the side
+        let tile = |(a, b): (i64, i64), k: i64, t: i64| (a * k + b.abs() * t, b * k + a.abs() * t);
+
+        let mut increments = [0; 4];
+        for (side, (dir, size)) in sides.iter().enumerate() {
+            let mut step = None;
+            for k in [r, r - 1] {
+                for t in -r..=r {
+                    for i in 0..h {
+                        for j in 0..w {
+                            match (
+                                self.get(tile(*dir, k, t), (i, j)),
+                                self.get(tile(*dir, k - 1, t), (i, j)),
+                            ) {
+                                (None, None) => {}
+                                (Some(outer), Some(inner)) if outer > inner => {
+                                    if *step.get_or_insert(outer - inner) != outer - inner {
+                                        return None;
+                                    }
+                                }
+                                _ => return None,
+                            }
                         }
                     }
                 }
             }
+            // nothing on this side is reachable, so any increment will do
+            increments[side] = step.unwrap_or(*size);
         }
+        Some(increments)
     }
 
-    reachable
-}
+    fn count_reachable(&self, limit: usize, [up, down, left, right]: Increments) -> usize {
+        let r = self.radius as i64;
+        let (h, w) = self.shape;
+        let mut reachable = 0;
+        let mut corners: HashMap<(usize, usize, usize), usize> = HashMap::new();
+
+        for ti in -r..=r {
+            for tj in -r..=r {
+                let vertical = if ti < 0 { up } else { down };
+                let horizontal = if tj < 0 { left } else { right };
+                for i in 0..h {
+                    for j in 0..w {
+                        let Some(d) = self.get((ti, tj), (i, j)) else {
+                            continue;
+                        };
+                        reachable += match (ti.abs() == r, tj.abs() == r) {
+                            (false, false) => (d <= limit && d % 2 == limit % 2) as usize,
+                            (true, false) => count_progression(d, vertical, limit),
+                            (false, true) => count_progression(d, horizontal, limit),
+                            (true, true) => {
+                                *corners.entry((d, vertical, horizontal)).or_insert(0) += 1;
+                                0
+                            }
+                        };
+                    }
+                }
+            }
+        }
 
-fn count_reachable_limit(map: &Vec<Vec<bool>>, start: (usize, usize), limit: usize) -> usize {
-    static CACHE: OnceLock<Mutex<HashMap<((usize, usize), usize), usize>>> = OnceLock::new();
-    let mut cache = CACHE
-        .get_or_init(|| Mutex::new(HashMap::new()))
-        .lock()
-        .unwrap();
+        // corner tiles extend in both directions, so sum over every row of tiles
+        for ((d, vertical, horizontal), cells) in corners {
+            let row_counts: usize = (0..)
+                .map(|a| d + a * vertical)
+                .take_while(|x| *x <= limit)
+                .map(|x| count_progression(x, horizontal, limit))
+                .sum();
+            reachable += cells * row_counts;
+        }
 
-    if let Some(value) = cache.get(&(start, limit)) {
-        // println!("returning cached value");
-        *value
-    } else {
-        let value = count_reachable(map, start, limit % 2 == 0, Some(limit));
-        cache.insert((start, limit), value);
-        value
+        reachable
     }
 }
 
-fn count_reachable_nolimit(map: &Vec<Vec<bool>>, start: (usize, usize), even: bool) -> usize {
-    static CACHE: OnceLock<Mutex<HashMap<((usize, usize), bool), usize>>> = OnceLock::new();
-    let mut cache = CACHE
-        .get_or_init(|| Mutex::new(HashMap::new()))
-        .lock()
-        .unwrap();
-
-    if let Some(value) = cache.get(&(start, even)) {
-        // println!("returning cached value");
-        *value
-    } else {
-        let value = count_reachable(map, start, even, None);
-        cache.insert((start, even), value);
-        value
+// walks the repeated garden directly, one BFS layer at a time; sizes[d] is
+// the number of cells first reached after d steps
+fn layer_sizes(map: &[Vec<bool>], start: (usize, usize), depth: usize) -> Vec<usize> {
+    let (h, w) = (map.len() as i64, map[0].len() as i64);
+    let side = 2 * depth + 1;
+    let mut visited = vec![0u64; (side * side).div_ceil(64)];
+    let index =
+        |(i, j): (i64, i64)| (i + depth as i64) as usize * side + (j + depth as i64) as usize;
+
+    let mut frontier = vec![(0, 0)];
+    let k = index((0, 0));
+    visited[k / 64] |= 1 << (k % 64);
+
+    let mut sizes = vec![1];
+    for _ in 1..=depth {
+        let mut next = Vec::new();
+        for (i, j) in frontier {
+            for (k, l) in [(i - 1, j), (i + 1, j), (i, j - 1), (i, j + 1)] {
+                let row = (start.0 as i64 + k).rem_euclid(h) as usize;
+                let col = (start.1 as i64 + l).rem_euclid(w) as usize;
+                let bit = index((k, l));
+                if map[row][col] && visited[bit / 64] & (1 << (bit % 64)) == 0 {
+                    visited[bit / 64] |= 1 << (bit % 64);
+                    next.push((k, l));
+                }
+            }
+        }
+        sizes.push(next.len());
+        frontier = next;
     }
+
+    sizes
 }
 
-fn go_sideways(map: &Vec<Vec<bool>>, start: (usize, usize), limit: usize) -> usize {
-    // figure out how many repetitions are fully reachable and can be computed
-    // quickly
+fn count_reachable_wrapping(map: &[Vec<bool>], start: (usize, usize), limit: usize) -> usize {
+    layer_sizes(map, start, limit)
+        .iter()
+        .skip(limit % 2)
+        .step_by(2)
+        .sum()
+}
 
-    let full: usize = match start {
-        (65, _) => 195,
-        _ => 260,
-    };
+// Far enough out, the layer sizes of a repeating garden grow by a fixed
+// amount every `period` steps, one amount per step within the period. Finds
+// the shortest even period that holds over the second half of `sizes` and
+// sums the extended layers up to `limit` in closed form.
+fn extrapolate_layers(sizes: &[usize], limit: usize) -> Option<usize> {
+    let depth = sizes.len() - 1;
+    let reached = |upto: usize| -> usize { sizes[..=upto].iter().skip(limit % 2).step_by(2).sum() };
+    if limit <= depth {
+        return Some(reached(limit));
+    }
 
-    let n = if limit > full {
-        (limit - full) / 131
-    } else {
-        0
-    };
+    let s = |d: usize| sizes[d] as i128;
+    let period = (2..=depth / 6).step_by(2).find(|p| {
+        (depth / 2 + 2 * p..=depth).all(|d| s(d) - s(d - p) == s(d - p) - s(d - 2 * p))
+    })?;
 
-    let mut reachable = 0;
-    reachable += (n / 2) * count_reachable_nolimit(map, start, true);
-    reachable += (n / 2) * count_reachable_nolimit(map, start, false);
-    if n % 2 == 1 {
-        reachable += if limit % 2 == 0 {
-            count_reachable_nolimit(map, start, true)
-        } else {
-            count_reachable_nolimit(map, start, false)
-        };
-    }
-
-    let mut l = limit - (n * 131);
-    loop {
-        reachable += count_reachable_limit(map, start, l);
-
-        if l >= 131 {
-            l -= 131;
-        } else {
-            break;
-        }
+    let mut reachable = reached(depth) as i128;
+    for d in (depth + 1 - period..=depth).filter(|d| d % 2 == limit % 2) {
+        let growth = s(d) - s(d - period);
+        let m = ((limit - d) / period) as i128;
+        reachable += m * s(d) + growth * m * (m + 1) / 2;
     }
+    usize::try_from(reachable).ok()
+}
 
-    reachable
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
 }
 
-fn count_all_reachable(map: &Vec<Vec<bool>>) -> usize {
-    static LIMIT: usize = 26_501_365;
+fn lcm(a: usize, b: usize) -> usize {
+    a / gcd(a, b) * b
+}
 
-    let mut reachable = 0;
+// fits reachable counts at offset + n * period with a quadratic in n, using
+// Newton's forward differences so everything stays in integers
+fn extrapolate_quadratic(
+    map: &[Vec<bool>],
+    start: (usize, usize),
+    period: usize,
+    offset: usize,
+    limit: usize,
+) -> Result<usize, String> {
+    if limit < offset || !(limit - offset).is_multiple_of(period) {
+        return Err(format!(
+            "{limit} is not {offset} plus a multiple of {period}"
+        ));
+    }
 
-    // start row: start plus go left plus go right
-    reachable += count_reachable_limit(map, (65, 65), LIMIT);
-    reachable += go_sideways(map, (65, 0), LIMIT - 66);
-    reachable += go_sideways(map, (65, 130), LIMIT - 66);
+    let y: Vec<i128> = (0..4)
+        .map(|n| count_reachable_wrapping(map, start, offset + n * period) as i128)
+        .collect();
+    let d1 = y[1] - y[0];
+    let d2 = y[2] - 2 * y[1] + y[0];
+    let fit = |n: i128| y[0] + n * d1 + n * (n - 1) / 2 * d2;
+
+    if fit(3) != y[3] {
+        return Err(format!(
+            "growth is not quadratic: samples {:?} predict {} at n = 3",
+            y,
+            fit(3)
+        ));
+    }
 
-    // rows above: sub 66, then repeatedly sub 131 until < 131 remain
-    let mut l = LIMIT - 66;
-    loop {
-        if l >= 260 {
-            reachable += count_reachable_nolimit(map, (130, 65), l % 2 == 0);
-        } else {
-            reachable += count_reachable_limit(map, (130, 65), l);
-        }
+    let n = ((limit - offset) / period) as i128;
+    usize::try_from(fit(n)).map_err(|e| e.to_string())
+}
 
-        if l >= 66 {
-            reachable += go_sideways(map, (130, 0), l - 66);
-            reachable += go_sideways(map, (130, 130), l - 66);
-        }
+fn count_all_reachable(map: &[Vec<bool>], start: (usize, usize), limit: usize) -> Option<usize> {
+    // grow the block until it holds every walk, or the distances at its
+    // edge repeat tile by tile
+    let settled = (2..=8)
+        .map(|radius| Block::new(map, start, radius))
+        .find_map(|block| {
+            if block.contains(limit) {
+                Some(block.count_inside(limit))
+            } else {
+                block
+                    .increments()
+                    .map(|increments| block.count_reachable(limit, increments))
+            }
+        });
+
+    // without open rows or columns the block may never settle; walk the
+    // garden deeper and deeper until the layer sizes repeat or the walk
+    // covers the limit itself
+    settled.or_else(|| {
+        let (h, w) = (map.len(), map[0].len());
+        (0..)
+            .map(|k| ((16 * (h + w)) << k).min(limit))
+            .take_while(|depth| *depth <= 1 << 13)
+            .find_map(|depth| extrapolate_layers(&layer_sizes(map, start, depth), limit))
+    })
+}
 
-        if l >= 131 {
-            l -= 131
-        } else {
-            break;
-        }
+#[cfg(test)]
+static EXAMPLE: &str = "...........
+.....###.#.
+.###.##..#.
+..#.#...#..
+....#.#....
+.##..S####.
+.##..#...#.
+.......##..
+.##.#.####.
+.##..##.##.
+...........
+";
+
+#[cfg(test)]
+static EXAMPLE_COUNTS: [(usize, usize); 7] = [
+    (6, 16),
+    (10, 50),
+    (50, 1594),
+    (100, 6536),
+    (500, 167004),
+    (1000, 668697),
+    (5000, 16733044),
+];
+
+#[test]
+fn test_count_reachable_wrapping() {
+    let map = parse_input(EXAMPLE);
+    let start = find_start(EXAMPLE).unwrap();
+
+    for (limit, expected) in EXAMPLE_COUNTS {
+        assert_eq!(count_reachable_wrapping(&map, start, limit), expected);
     }
+}
 
-    // rows below: sub 66, then repeatedly sub 131 until < 131 remain
-    let mut l = LIMIT - 66;
-    loop {
-        if l >= 260 {
-            reachable += count_reachable_nolimit(map, (0, 65), l % 2 == 0);
-        } else {
-            reachable += count_reachable_limit(map, (0, 65), l);
-        }
+#[test]
+fn test_count_all_reachable() {
+    let map = parse_input(EXAMPLE);
+    let start = find_start(EXAMPLE).unwrap();
 
-        if l >= 66 {
-            reachable += go_sideways(map, (0, 0), l - 66);
-            reachable += go_sideways(map, (0, 130), l - 66);
-        }
+    for (limit, expected) in EXAMPLE_COUNTS {
+        assert_eq!(count_all_reachable(&map, start, limit), Some(expected));
+    }
 
-        if l >= 131 {
-            l -= 131
-        } else {
-            break;
-        }
+    for limit in (0..=120).chain((121..=400).step_by(7)) {
+        assert_eq!(
+            count_all_reachable(&map, start, limit),
+            Some(count_reachable_wrapping(&map, start, limit)),
+            "limit {limit}"
+        );
     }
+}
+
+#[test]
+fn test_count_all_reachable_without_open_lines() {
+    // every row and every column has a rock, so crossing a tile takes
+    // more than four steps
+    let garden = ".#..\n..S#\n#...\n..#.\n";
+    let map = parse_input(garden);
+    let start = find_start(garden).unwrap();
+
+    for limit in (0..=80).chain([333, 1000, 2001]) {
+        assert_eq!(
+            count_all_reachable(&map, start, limit),
+            Some(count_reachable_wrapping(&map, start, limit)),
+            "limit {limit}"
+        );
+    }
+
+    // the layer sizes settle well within 300 steps
+    let sizes = layer_sizes(&map, start, 300);
+    assert_eq!(
+        extrapolate_layers(&sizes, 2001),
+        Some(count_reachable_wrapping(&map, start, 2001))
+    );
+}
 
-    reachable
+#[test]
+fn test_extrapolate_quadratic() {
+    let map = parse_input(EXAMPLE);
+    let start = find_start(EXAMPLE).unwrap();
+
+    // the first few periods of the example are still settling
+    assert!(extrapolate_quadratic(&map, start, 11, 6, 5000).is_err());
+    assert_eq!(
+        extrapolate_quadratic(&map, start, 11, 39, 5000),
+        Ok(16733044)
+    );
+    assert_eq!(extrapolate_quadratic(&map, start, 11, 43, 1000), Ok(668697));
+    assert!(extrapolate_quadratic(&map, start, 11, 39, 5001).is_err());
+
+    // a garden that is wider than it is tall repeats every lcm(3, 5) steps
+    let garden = ".....\n.#S#.\n.....\n";
+    let map = parse_input(garden);
+    let start = find_start(garden).unwrap();
+    let period = lcm(map.len(), map[0].len());
+    assert_eq!(period, 15);
+    assert_eq!(
+        extrapolate_quadratic(&map, start, period, 37, 352),
+        Ok(count_reachable_wrapping(&map, start, 352))
+    );
 }
 
+static BRUTE_LIMIT: usize = 20_000;
+
 fn main() {
     let input = fs::read_to_string("input").expect("unable to read input");
     let map = parse_input(&input);
-
-    let answer = count_all_reachable(&map);
+    let start = find_start(&input).expect("unable to find start");
+    let limit = env::args()
+        .skip(1)
+        .find(|a| !a.starts_with("--"))
+        .map_or(26_501_365, |a| {
+            a.parse().expect("step limit must be a number")
+        });
+
+    let answer = if env::args().any(|a| a == "--brute") {
+        // the walk keeps one bit for every cell within `limit` steps
+        if limit > BRUTE_LIMIT {
+            eprintln!("--brute needs an explicit step limit of at most {BRUTE_LIMIT}, got {limit}");
+            return;
+        }
+        count_reachable_wrapping(&map, start, limit)
+    } else if env::args().any(|a| a == "--quadratic") {
+        // the garden repeats every lcm(h, w) steps in both directions at once
+        let period = lcm(map.len(), map[0].len());
+        extrapolate_quadratic(&map, start, period, limit % period, limit)
+            .expect("unable to extrapolate")
+    } else {
+        count_all_reachable(&map, start, limit).expect("distances never settled")
+    };
     println!("answer: {answer}");
 }
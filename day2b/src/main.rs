@@ -1,76 +1,156 @@
-use std::fs;
+use std::{collections::BTreeMap, env, fs};
 
-#[derive(PartialEq)]
-enum Color {
-    Red,
-    Green,
-    Blue,
+type Bag = BTreeMap<String, u32>;
+
+#[derive(Debug)]
+struct Game {
+    id: u32,
+    draws: Vec<Bag>,
 }
 
-struct Draw {
-    color: Color,
-    n: i32,
+fn parse_draw(draw_str: &str) -> Option<Bag> {
+    draw_str
+        .split(',')
+        .map(|cubes| {
+            let mut parts = cubes.split_whitespace();
+            let n = parts.next()?.parse::<u32>().ok()?;
+            let color = parts.next()?.to_string();
+            Some((color, n))
+        })
+        .collect()
 }
 
-fn parse_draw(draw_str: &str) -> Option<Draw> {
-    let mut draw_parts = draw_str.split_whitespace();
-    let n = draw_parts.next().unwrap().parse::<i32>().unwrap();
-    let color = draw_parts.next().unwrap();
-
-    match color {
-        "red" => Some(Draw {
-            color: Color::Red,
-            n,
-        }),
-        "green" => Some(Draw {
-            color: Color::Green,
-            n,
-        }),
-        "blue" => Some(Draw {
-            color: Color::Blue,
-            n,
-        }),
-        _ => None,
-    }
+fn parse_game(game_str: &str) -> Option<Game> {
+    let (id_str, draws_str) = game_str.split_once(':')?;
+    let id = id_str.strip_prefix("Game ")?.parse::<u32>().ok()?;
+    let draws = draws_str
+        .split(';')
+        .map(parse_draw)
+        .collect::<Option<_>>()?;
+
+    Some(Game { id, draws })
 }
 
-fn parse_draws(hand_str: &str) -> Vec<Draw> {
-    let draws = hand_str.split(',');
-    draws.flat_map(parse_draw).collect()
+// "red=12,green=13,blue=14"
+fn parse_bag(bag_str: &str) -> Option<Bag> {
+    bag_str
+        .split(',')
+        .map(|c| {
+            let (color, n) = c.split_once('=')?;
+            Some((color.trim().to_string(), n.trim().parse::<u32>().ok()?))
+        })
+        .collect()
 }
 
-fn max_draw(draws: &Vec<Draw>, color: Color) -> i32 {
-    draws
-        .iter()
-        .filter_map(|d| if d.color == color { Some(d.n) } else { None })
-        .max()
-        .unwrap_or(0)
+impl Game {
+    fn min_bag(&self) -> Bag {
+        let mut bag = Bag::new();
+        for (color, n) in self.draws.iter().flatten() {
+            let max = bag.entry(color.to_string()).or_insert(0);
+            *max = (*max).max(*n);
+        }
+        bag
+    }
+
+    fn is_possible(&self, bag: &Bag) -> bool {
+        self.min_bag()
+            .iter()
+            .all(|(color, n)| bag.get(color).is_some_and(|m| n <= m))
+    }
+
+    // a color the game never shows needs no cubes, which makes the power 0
+    fn power(&self, colors: &[String]) -> u32 {
+        let bag = self.min_bag();
+        colors
+            .iter()
+            .map(|c| bag.get(c).copied().unwrap_or(0))
+            .product()
+    }
 }
 
-fn game_power(game_str: &str) -> i32 {
-    let mut game_and_hands = game_str.split(':');
-    game_and_hands.next();
+fn all_colors(games: &[Game]) -> Vec<String> {
+    let mut colors: Vec<String> = games.iter().flat_map(|g| g.min_bag().into_keys()).collect();
+    colors.sort();
+    colors.dedup();
+    colors
+}
 
-    let hands_str = game_and_hands.next().unwrap();
-    let hands = hands_str.split(';');
+#[test]
+fn test_game() {
+    let input = "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
+Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
+Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
+Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green
+";
+    let games: Vec<Game> = input
+        .lines()
+        .map(parse_game)
+        .collect::<Option<_>>()
+        .unwrap();
+    let colors = all_colors(&games);
 
-    let draws = hands.flat_map(parse_draws).collect();
+    let bag = parse_bag("red=12,green=13,blue=14").unwrap();
+    let possible: Vec<u32> = games
+        .iter()
+        .filter(|g| g.is_possible(&bag))
+        .map(|g| g.id)
+        .collect();
+    assert_eq!(possible, vec![1, 2, 5]);
 
-    let max_red = max_draw(&draws, Color::Red);
-    let max_green = max_draw(&draws, Color::Green);
-    let max_blue = max_draw(&draws, Color::Blue);
+    let powers: Vec<u32> = games.iter().map(|g| g.power(&colors)).collect();
+    assert_eq!(powers, vec![48, 12, 1560, 630, 36]);
 
-    println!("{game_str}");
-    println!("red: {:?}", max_red);
-    println!("green: {:?}", max_green);
-    println!("blue: {:?}", max_blue);
+    // colors other than red, green and blue are kept, not dropped
+    let game = parse_game("Game 7: 2 teal, 1 red; 5 teal").unwrap();
+    assert_eq!(game.min_bag(), parse_bag("red=1,teal=5").unwrap());
+    assert!(!game.is_possible(&bag));
+    assert!(game.is_possible(&parse_bag("teal=5, red=3").unwrap()));
+}
 
-    max_red * max_green * max_blue
+fn format_bag(bag: &Bag) -> String {
+    let cubes: Vec<String> = bag.iter().map(|(c, n)| format!("{n} {c}")).collect();
+    cubes.join(", ")
 }
 
 fn main() {
     let input = fs::read_to_string("input").expect("unable to read input");
-    let answer: i32 = input.lines().map(game_power).sum();
+    let games: Vec<Game> = input
+        .lines()
+        .map(|l| parse_game(l).unwrap_or_else(|| panic!("unable to parse {l:?}")))
+        .collect();
+    let colors = all_colors(&games);
 
-    println!("answer: {answer}");
+    let args: Vec<String> = env::args().collect();
+    match args.get(1).map(|a| a.as_str()) {
+        Some("possible") => {
+            let bag_str = args
+                .get(2)
+                .map_or("red=12,green=13,blue=14", |b| b.as_str());
+            let bag = parse_bag(bag_str).expect("bag should look like red=12,green=13");
+            let mut answer = 0;
+            for game in games.iter().filter(|g| g.is_possible(&bag)) {
+                println!("game {}", game.id);
+                answer += game.id;
+            }
+            println!("answer: {answer}");
+        }
+        Some("min-bag") => {
+            for game in &games {
+                println!("game {}: {}", game.id, format_bag(&game.min_bag()));
+            }
+        }
+        Some("power") | None => {
+            let mut answer = 0;
+            for game in &games {
+                let power = game.power(&colors);
+                if args.len() > 1 {
+                    println!("game {}: {power}", game.id);
+                }
+                answer += power;
+            }
+            println!("answer: {answer}");
+        }
+        Some(other) => eprintln!("unknown query {other:?}; try possible, min-bag or power"),
+    }
 }
@@ -1,74 +1,241 @@
 use once_cell::sync::Lazy;
 use regex::Regex;
-use std::ops::Bound::{Excluded, Included};
-use std::{collections::BTreeMap, fs};
-
-fn get_parts(input: &str, part_symbol: char) -> Vec<(usize, usize)> {
-    input
-        .lines()
-        .enumerate()
-        .flat_map(|(i, line)| {
-            line.chars().enumerate().filter_map(
-                move |(j, c)| {
-                    if c == part_symbol {
-                        Some((i, j))
-                    } else {
-                        None
-                    }
-                },
-            )
-        })
-        .collect()
+use std::{
+    collections::{BTreeMap, HashMap},
+    env, fs,
+};
+
+#[derive(Debug)]
+struct Number {
+    row: usize,
+    start: usize,
+    end: usize,
+    value: u32,
 }
 
-fn get_part_numbers(input: &str) -> BTreeMap<(usize, (usize, usize)), u32> {
-    static PATTERN: Lazy<Regex> = Lazy::new(|| Regex::new(r"\d+").unwrap());
+#[derive(Debug)]
+struct Symbol {
+    row: usize,
+    col: usize,
+    c: char,
+}
 
-    let mut part_nums = BTreeMap::new();
-    for (i, line) in input.lines().enumerate() {
-        for m in PATTERN.find_iter(line) {
-            let num = m.as_str().parse::<u32>().unwrap();
-            part_nums.insert((i, (m.start(), m.end())), num);
+// numbers and symbols are the two sides of a bipartite graph, joined when
+// a symbol sits in the ring of cells around a number
+struct Schematic {
+    numbers: Vec<Number>,
+    symbols: Vec<Symbol>,
+    number_edges: Vec<Vec<usize>>,
+    symbol_edges: Vec<Vec<usize>>,
+}
+
+impl Schematic {
+    fn new(input: &str) -> Schematic {
+        static PATTERN: Lazy<Regex> = Lazy::new(|| Regex::new(r"\d+").unwrap());
+
+        let mut numbers = Vec::new();
+        let mut symbols = Vec::new();
+        for (row, line) in input.lines().enumerate() {
+            for m in PATTERN.find_iter(line) {
+                let value = m.as_str().parse::<u32>().unwrap();
+                numbers.push(Number {
+                    row,
+                    start: m.start(),
+                    end: m.end(),
+                    value,
+                });
+            }
+            for (col, c) in line.chars().enumerate() {
+                if !c.is_ascii_digit() && c != '.' {
+                    symbols.push(Symbol { row, col, c });
+                }
+            }
+        }
+
+        let positions: HashMap<(usize, usize), usize> = symbols
+            .iter()
+            .enumerate()
+            .map(|(k, s)| ((s.row, s.col), k))
+            .collect();
+
+        let mut number_edges = vec![Vec::new(); numbers.len()];
+        let mut symbol_edges = vec![Vec::new(); symbols.len()];
+        for (n, number) in numbers.iter().enumerate() {
+            for i in number.row.saturating_sub(1)..=number.row + 1 {
+                for j in number.start.saturating_sub(1)..=number.end {
+                    if let Some(s) = positions.get(&(i, j)) {
+                        number_edges[n].push(*s);
+                        symbol_edges[*s].push(n);
+                    }
+                }
+            }
+        }
+
+        Schematic {
+            numbers,
+            symbols,
+            number_edges,
+            symbol_edges,
         }
     }
 
-    part_nums
-}
+    fn part_numbers(&self) -> Vec<&Number> {
+        self.numbers_touching(|_| true)
+    }
+
+    fn numbers_touching<F: Fn(char) -> bool>(&self, class: F) -> Vec<&Number> {
+        self.numbers
+            .iter()
+            .zip(&self.number_edges)
+            .filter(|(_, edges)| edges.iter().any(|s| class(self.symbols[*s].c)))
+            .map(|(n, _)| n)
+            .collect()
+    }
 
-fn maybe_gear_ratio(
-    part_nums: &BTreeMap<(usize, (usize, usize)), u32>,
-    gear: &(usize, usize),
-) -> Option<u32> {
-    let (i, j) = gear;
-    let min_i = if i > &0 { i - 1 } else { 0 };
-    let adjacent_nums: Vec<_> = part_nums
-        .range((Included((min_i, (0, 0))), Excluded((i + 2, (0, 0)))))
-        .filter_map(|((_, (n, m)), part_num)| {
-            if (&(n + 1) >= j && n <= &(j + 1)) || (m >= j && m <= &(j + 2)) {
-                Some(part_num)
-            } else {
-                None
+    // symbols `c` with exactly `k` neighboring numbers, along with those numbers
+    fn gears(&self, c: char, k: usize) -> Vec<(&Symbol, Vec<&Number>)> {
+        self.symbols
+            .iter()
+            .zip(&self.symbol_edges)
+            .filter(|(s, edges)| s.c == c && edges.len() == k)
+            .map(|(s, edges)| (s, edges.iter().map(|n| &self.numbers[*n]).collect()))
+            .collect()
+    }
+
+    fn render(&self, input: &str) -> String {
+        static GREEN: &str = "\x1b[32m";
+        static RED: &str = "\x1b[31m";
+        static YELLOW: &str = "\x1b[1;33m";
+        static MAGENTA: &str = "\x1b[35m";
+        static CYAN: &str = "\x1b[36m";
+        static RESET: &str = "\x1b[0m";
+
+        let mut colors: HashMap<(usize, usize), &str> = HashMap::new();
+        for (number, edges) in self.numbers.iter().zip(&self.number_edges) {
+            let color = if edges.is_empty() { RED } else { GREEN };
+            for j in number.start..number.end {
+                colors.insert((number.row, j), color);
             }
-        })
-        .collect();
+        }
+        for (symbol, edges) in self.symbols.iter().zip(&self.symbol_edges) {
+            let color = match (symbol.c, edges.len()) {
+                ('*', 2) => YELLOW,
+                ('*', _) => MAGENTA,
+                _ => CYAN,
+            };
+            colors.insert((symbol.row, symbol.col), color);
+        }
 
-    if adjacent_nums.len() == 2 {
-        println!("found gear: {:?}", (i, j));
-        Some(adjacent_nums.iter().map(|n| **n).product())
-    } else {
-        None
+        let mut out = String::new();
+        for (i, line) in input.lines().enumerate() {
+            for (j, c) in line.chars().enumerate() {
+                match colors.get(&(i, j)) {
+                    Some(color) => out += &format!("{color}{c}{RESET}"),
+                    None => out.push(c),
+                }
+            }
+            out.push('\n');
+        }
+
+        out += &format!(
+            "\n{GREEN}123{RESET} part number  {RED}123{RESET} not a part  {YELLOW}*{RESET} gear  {MAGENTA}*{RESET} not a gear  {CYAN}#{RESET} other symbol\n\n"
+        );
+
+        // one row per symbol character, tallying the numbers around it
+        let mut summary: BTreeMap<char, (usize, usize, usize, u32)> = BTreeMap::new();
+        for (symbol, edges) in self.symbols.iter().zip(&self.symbol_edges) {
+            let row = summary.entry(symbol.c).or_default();
+            row.0 += 1;
+            row.1 += (edges.len() == 2) as usize;
+            row.2 += edges.len();
+            row.3 += edges.iter().map(|n| self.numbers[*n].value).sum::<u32>();
+        }
+        out += "symbol  count  two neighbors  numbers  sum\n";
+        for (c, (count, two, numbers, sum)) in summary {
+            out += &format!("{c:>6}  {count:>5}  {two:>13}  {numbers:>7}  {sum}\n");
+        }
+
+        out
     }
 }
 
-fn main() {
-    let input = fs::read_to_string("input").expect("unable to read input");
-    let maybe_gears = get_parts(&input, '*');
-    let part_numbers = get_part_numbers(&input);
+#[test]
+fn test_schematic() {
+    let input = "467..114..
+...*......
+..35..633.
+......#...
+617*......
+.....+.58.
+..592.....
+......755.
+...$.*....
+.664.598..
+";
+    let schematic = Schematic::new(input);
+
+    let parts: u32 = schematic.part_numbers().iter().map(|n| n.value).sum();
+    assert_eq!(parts, 4361);
 
-    let answer: u32 = maybe_gears
+    let ratios: u32 = schematic
+        .gears('*', 2)
         .iter()
-        .filter_map(|g| maybe_gear_ratio(&part_numbers, g))
+        .map(|(_, ns)| ns.iter().map(|n| n.value).product::<u32>())
         .sum();
+    assert_eq!(ratios, 467835);
+
+    let lonely: Vec<_> = schematic
+        .gears('*', 1)
+        .iter()
+        .map(|(s, _)| (s.row, s.col))
+        .collect();
+    assert_eq!(lonely, vec![(4, 3)]);
+
+    let dollars: Vec<u32> = schematic
+        .numbers_touching(|c| c == '$')
+        .iter()
+        .map(|n| n.value)
+        .collect();
+    assert_eq!(dollars, vec![664]);
+
+    let rendered = schematic.render(input);
+    assert!(rendered.starts_with("\x1b[32m4\x1b[0m\x1b[32m6\x1b[0m\x1b[32m7\x1b[0m..\x1b[31m1"));
+    assert!(rendered.contains("\x1b[1;33m*\x1b[0m"));
+    assert!(rendered.contains("\x1b[35m*\x1b[0m"));
+    assert!(rendered.contains("     *      3              2        5  "));
+}
+
+fn main() {
+    let input = fs::read_to_string("input").expect("unable to read input");
+    let schematic = Schematic::new(&input);
+
+    let args: Vec<String> = env::args().collect();
+    let answer: u32 = match args.get(1).map(|a| a.as_str()) {
+        Some("render") => {
+            print!("{}", schematic.render(&input));
+            return;
+        }
+        Some("parts") => schematic.part_numbers().iter().map(|n| n.value).sum(),
+        Some("touching") => {
+            let class = args.get(2).expect("touching takes a list of symbols");
+            schematic
+                .numbers_touching(|c| class.contains(c))
+                .iter()
+                .map(|n| n.value)
+                .sum()
+        }
+        Some("gears") | None => {
+            let k = args
+                .get(2)
+                .map_or(2, |k| k.parse().expect("gears takes a neighbor count"));
+            schematic
+                .gears('*', k)
+                .iter()
+                .map(|(_, ns)| ns.iter().map(|n| n.value).product::<u32>())
+                .sum()
+        }
+        Some(other) => panic!("unknown query {other:?}; try parts, gears, touching or render"),
+    };
 
     println!("answer: {answer}");
 }
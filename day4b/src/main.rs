@@ -1,59 +1,213 @@
-use std::{cell::Cell, fs};
+use std::{env, fs};
 
 #[derive(Debug)]
 struct Card {
-    copies: Cell<i32>,
-    winning: Vec<i32>,
-    have: Vec<i32>,
+    winning: u128,
+    have: u128,
 }
 
-fn parse_line(line: &str) -> Card {
-    let mut parts = line.split(':');
-    parts.next();
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Overflow {
+    Clamp,
+    Reject,
+}
 
-    let mut numbers = parts.next().unwrap().split('|');
+fn parse_numbers(numbers_str: &str) -> Option<u128> {
+    numbers_str
+        .split_whitespace()
+        .map(|s| s.parse::<u32>().ok().filter(|n| *n < 128))
+        .try_fold(0, |mask, n| Some(mask | 1 << n?))
+}
 
-    let winning_str = numbers.next().expect("no winning numbers");
-    let have_str = numbers.next().expect("no have numbers");
+fn parse_line(line: &str) -> Option<Card> {
+    let (_, numbers) = line.split_once(':')?;
+    let (winning_str, have_str) = numbers.split_once('|')?;
 
-    let winning: Vec<_> = winning_str
-        .split_whitespace()
-        .map(|s| s.parse::<i32>().unwrap())
-        .collect();
-    let have: Vec<_> = have_str
-        .split_whitespace()
-        .map(|s| s.parse::<i32>().unwrap())
-        .collect();
+    Some(Card {
+        winning: parse_numbers(winning_str)?,
+        have: parse_numbers(have_str)?,
+    })
+}
 
-    Card {
-        copies: Cell::new(1),
-        winning,
-        have,
+impl Card {
+    fn wins(&self) -> usize {
+        (self.winning & self.have).count_ones() as usize
+    }
+
+    fn matching(&self) -> Vec<u32> {
+        let mask = self.winning & self.have;
+        (0..128).filter(|n| mask & 1 << n != 0).collect()
+    }
+
+    fn points(&self) -> u128 {
+        match self.wins() {
+            0 => 0,
+            w => 1 << (w - 1),
+        }
     }
 }
 
-fn process_cards(cards: &Vec<Card>) -> i32 {
-    for i in 0..cards.len() {
-        let Card {
-            copies,
-            have,
-            winning,
-        } = &cards[i];
-        let wins = have.iter().filter(|n| winning.contains(n)).count();
+fn process_cards(cards: &[Card], overflow: Overflow) -> Result<Vec<u64>, String> {
+    // diff[i] holds the change in extra copies from card i - 1 to card i
+    let mut diff = vec![0i64; cards.len() + 1];
+    let mut copies = Vec::with_capacity(cards.len());
+    let mut extra = 0;
 
-        for j in 0..wins {
-            let Card { copies: n, .. } = &cards[i + j + 1];
-            n.set(n.get() + copies.get());
+    for (i, card) in cards.iter().enumerate() {
+        extra += diff[i];
+        let n = 1 + extra as u64;
+        copies.push(n);
+
+        let mut last = i + card.wins();
+        if last >= cards.len() {
+            match overflow {
+                Overflow::Clamp => last = cards.len() - 1,
+                Overflow::Reject => {
+                    return Err(format!(
+                        "card {} wins copies of cards up to {}, but there are only {}",
+                        i + 1,
+                        last + 1,
+                        cards.len()
+                    ))
+                }
+            }
+        }
+        if last > i {
+            diff[i + 1] += n as i64;
+            diff[last + 1] -= n as i64;
         }
     }
 
-    cards.iter().map(|Card { copies, .. }| copies.get()).sum()
+    Ok(copies)
+}
+
+#[test]
+fn test_process_cards() {
+    let input = "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
+Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
+Card 5: 87 83 26 28 32 | 88 30 23 11 85 67 18 77
+Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11
+";
+    let cards: Vec<Card> = input
+        .lines()
+        .map(parse_line)
+        .collect::<Option<_>>()
+        .unwrap();
+    let copies = process_cards(&cards, Overflow::Reject).unwrap();
+    assert_eq!(copies, vec![1, 2, 4, 8, 14, 1]);
+    assert_eq!(copies.iter().sum::<u64>(), 30);
+
+    // the last card's wins run past the end of the table
+    let cards: Vec<Card> = input
+        .lines()
+        .take(2)
+        .map(parse_line)
+        .collect::<Option<_>>()
+        .unwrap();
+    assert_eq!(process_cards(&cards, Overflow::Clamp), Ok(vec![1, 2]));
+    assert!(process_cards(&cards, Overflow::Reject).is_err());
+
+    assert!(parse_line("Card 1: 128 | 1").is_none());
+}
+
+// one row per card; `from` lists the cards that won it, with how many copies each sent
+fn report(cards: &[Card], copies: &[u64], csv: bool) -> String {
+    let mut from: Vec<Vec<(usize, u64)>> = vec![Vec::new(); cards.len()];
+    for (i, card) in cards.iter().enumerate() {
+        let last = (i + card.wins()).min(cards.len() - 1);
+        for sources in &mut from[i + 1..=last] {
+            sources.push((i + 1, copies[i]));
+        }
+    }
+
+    let mut out = if csv {
+        "card,matching,points,copies,from\n".to_string()
+    } else {
+        format!(
+            "{:>5}  {:<30}  {:>6}  {:>8}  from\n",
+            "card", "matching", "points", "copies"
+        )
+    };
+
+    for (i, card) in cards.iter().enumerate() {
+        let matching: Vec<String> = card.matching().iter().map(|n| n.to_string()).collect();
+        let sources: Vec<String> = from[i].iter().map(|(c, n)| format!("{c}x{n}")).collect();
+        out += &if csv {
+            format!(
+                "{},{},{},{},{}\n",
+                i + 1,
+                matching.join(" "),
+                card.points(),
+                copies[i],
+                sources.join(" ")
+            )
+        } else {
+            format!(
+                "{:>5}  {:<30}  {:>6}  {:>8}  {}\n",
+                i + 1,
+                matching.join(" "),
+                card.points(),
+                copies[i],
+                sources.join(" ")
+            )
+        };
+    }
+
+    out
+}
+
+#[test]
+fn test_report() {
+    let input = "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
+Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
+Card 5: 87 83 26 28 32 | 88 30 23 11 85 67 18 77
+Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11
+";
+    let cards: Vec<Card> = input
+        .lines()
+        .map(parse_line)
+        .collect::<Option<_>>()
+        .unwrap();
+    let csv = report(
+        &cards,
+        &process_cards(&cards, Overflow::Clamp).unwrap(),
+        true,
+    );
+    let rows: Vec<&str> = csv.lines().collect();
+    assert_eq!(rows[0], "card,matching,points,copies,from");
+    assert_eq!(rows[1], "1,17 48 83 86,8,1,");
+    assert_eq!(rows[4], "4,84,1,8,1x1 2x2 3x4");
+    assert_eq!(rows[6], "6,,0,1,");
+
+    // every number from 0 to 127 matches
+    let all: Vec<String> = (0..128).map(|n| n.to_string()).collect();
+    let card = parse_line(&format!("Card 1: {} | {}", all.join(" "), all.join(" "))).unwrap();
+    assert_eq!(card.points(), 1 << 127);
 }
 
 fn main() {
+    let overflow = if env::args().any(|a| a == "--reject") {
+        Overflow::Reject
+    } else {
+        Overflow::Clamp
+    };
     let input = fs::read_to_string("input").expect("unable to read input");
-    let cards: Vec<Card> = input.lines().map(parse_line).collect();
-    let answer = process_cards(&cards);
+    let cards: Vec<Card> = input
+        .lines()
+        .map(|l| parse_line(l).unwrap_or_else(|| panic!("unable to parse {l:?}")))
+        .collect();
+    let copies = process_cards(&cards, overflow).expect("unable to process cards");
+    let answer: u64 = copies.iter().sum();
 
-    println!("answer: {answer}");
+    let csv = env::args().any(|a| a == "--csv");
+    if csv || env::args().any(|a| a == "--report") {
+        print!("{}", report(&cards, &copies, csv));
+    }
+    if !csv {
+        println!("answer: {answer}");
+    }
 }
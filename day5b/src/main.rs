@@ -1,5 +1,5 @@
 use itertools::Itertools;
-use std::fs;
+use std::{env, fs};
 
 #[derive(Debug)]
 struct Map {
@@ -88,7 +88,7 @@ fn get_locations(input: &str) -> i64 {
     let mut lines = input.lines();
 
     let seeds_line = lines.next().expect("no seeds line");
-    let seeds_raw = seeds_line.split(":").skip(1).next().expect("no seeds");
+    let seeds_raw = seeds_line.split(':').nth(1).expect("no seeds");
     let seeds_ranges: Vec<_> = seeds_raw
         .split_whitespace()
         .flat_map(|s| s.parse::<i64>())
@@ -124,8 +124,140 @@ fn get_locations(input: &str) -> i64 {
         .unwrap()
 }
 
+impl Map {
+    fn source_overlaps(&self) -> Vec<(usize, usize)> {
+        self.overlaps(|e| (e.key_start, e.key_start + e.range_len))
+    }
+
+    // two entries landing on the same values make the map non-invertible
+    fn destination_overlaps(&self) -> Vec<(usize, usize)> {
+        self.overlaps(|e| (e.value_start, e.value_start + e.range_len))
+    }
+
+    fn overlaps<F: Fn(&Entry) -> (i64, i64)>(&self, range: F) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+        for (a, b) in (0..self.entries.len()).tuple_combinations() {
+            let (a_start, a_end) = range(&self.entries[a]);
+            let (b_start, b_end) = range(&self.entries[b]);
+            if a_start < b_end && b_start < a_end {
+                pairs.push((a, b));
+            }
+        }
+        pairs
+    }
+
+    // keys between the lowest and highest source range that fall through to identity
+    fn gaps(&self) -> Vec<(i64, i64)> {
+        let mut gaps = Vec::new();
+        let mut covered_to: Option<i64> = None;
+        for e in self.entries.iter().sorted_by_key(|e| e.key_start) {
+            if let Some(end) = covered_to {
+                if e.key_start > end {
+                    gaps.push((end, e.key_start));
+                }
+            }
+            let end = e.key_start + e.range_len;
+            covered_to = Some(covered_to.map_or(end, |c| c.max(end)));
+        }
+        gaps
+    }
+
+    // entries sorted by key with overlaps resolved the way `get` does (first
+    // entry wins) and contiguous entries with the same offset merged
+    fn normalized(&self) -> Map {
+        let mut segments: Vec<(i64, i64, i64)> = Vec::new();
+        for e in &self.entries {
+            let mut pieces = vec![(e.key_start, e.key_start + e.range_len)];
+            for (start, end, _) in &segments {
+                pieces = pieces
+                    .into_iter()
+                    .flat_map(|(a, b)| [(a, b.min(*start)), (a.max(*end), b)])
+                    .filter(|(a, b)| a < b)
+                    .collect();
+            }
+            let offset = e.value_start - e.key_start;
+            segments.extend(pieces.into_iter().map(|(a, b)| (a, b, offset)));
+        }
+        segments.sort();
+
+        let mut entries: Vec<Entry> = Vec::new();
+        for (start, end, offset) in segments {
+            match entries.last_mut() {
+                Some(last)
+                    if last.key_start + last.range_len == start
+                        && last.value_start - last.key_start == offset =>
+                {
+                    last.range_len += end - start
+                }
+                _ => entries.push(Entry::new(start, start + offset, end - start)),
+            }
+        }
+
+        Map { entries }
+    }
+}
+
+#[test]
+fn test_map_validation() {
+    let m = Map {
+        entries: vec![
+            Entry::from_str("100 10 5"),
+            Entry::from_str("200 12 2"),
+            Entry::from_str("105 15 3"),
+            Entry::from_str("102 30 2"),
+        ],
+    };
+
+    assert_eq!(m.source_overlaps(), vec![(0, 1)]);
+    assert_eq!(m.destination_overlaps(), vec![(0, 3)]);
+    assert_eq!(m.gaps(), vec![(18, 30)]);
+
+    // 12..14 belongs to the first entry, which continues into the third
+    let n = m.normalized();
+    let entries: Vec<_> = n
+        .entries
+        .iter()
+        .map(|e| (e.value_start, e.key_start, e.range_len))
+        .collect();
+    assert_eq!(entries, vec![(100, 10, 8), (102, 30, 2)]);
+    for key in 0..40 {
+        assert_eq!(n.get(key), m.get(key));
+    }
+}
+
+fn parse_maps(input: &str) -> Vec<(String, Map)> {
+    let names = input
+        .lines()
+        .filter_map(|l| l.strip_suffix(" map:"))
+        .map(|n| n.to_string());
+    let mut lines = input.lines();
+    names.map(|n| (n, next_map(&mut lines))).collect()
+}
+
+fn validate(input: &str) {
+    for (name, map) in parse_maps(input) {
+        println!("{name}:");
+        for (a, b) in map.source_overlaps() {
+            println!("  entries {} and {} overlap in source", a + 1, b + 1);
+        }
+        for (a, b) in map.destination_overlaps() {
+            println!("  entries {} and {} overlap in destination", a + 1, b + 1);
+        }
+        for (start, end) in map.gaps() {
+            println!("  {start}..{end} maps to itself");
+        }
+        for e in map.normalized().entries {
+            println!("  {} {} {}", e.value_start, e.key_start, e.range_len);
+        }
+    }
+}
+
 fn main() {
     let input = fs::read_to_string("input").expect("unable to read input");
+    if env::args().any(|a| a == "--validate") {
+        validate(&input);
+        return;
+    }
     let answer = get_locations(&input);
 
     println!("answer: {answer}");
@@ -1,5 +1,5 @@
 use counter::Counter;
-use std::fs;
+use std::{env, fs};
 
 #[derive(Debug)]
 struct Hand<'a> {
@@ -9,7 +9,7 @@ struct Hand<'a> {
     rank: (i32, [i32; 5]),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 enum Type {
     FiveOfAKind,
     FourOfAKind,
@@ -20,8 +20,8 @@ enum Type {
     HighCard,
 }
 
-impl Hand<'_> {
-    fn new(input_line: &str) -> Hand {
+impl<'a> Hand<'a> {
+    fn new(input_line: &'a str) -> Hand<'a> {
         let mut parts = input_line.split_whitespace();
         let cards = parts.next().expect("no cards");
         let bid = parts
@@ -87,7 +87,7 @@ fn get_rank(cards: &str, t: &Type) -> (i32, [i32; 5]) {
     };
 
     let cards_rank = [
-        card_rank(cards.chars().nth(0).unwrap()),
+        card_rank(cards.chars().next().unwrap()),
         card_rank(cards.chars().nth(1).unwrap()),
         card_rank(cards.chars().nth(2).unwrap()),
         card_rank(cards.chars().nth(3).unwrap()),
@@ -108,6 +108,102 @@ fn card_rank(card: char) -> i32 {
     }
 }
 
+// the type the cards make on their own, with jokers as plain jacks
+fn natural_type(cards: &str) -> Option<Type> {
+    let mut counts: Vec<usize> = cards
+        .chars()
+        .collect::<Counter<_>>()
+        .values()
+        .copied()
+        .collect();
+    counts.sort();
+    counts.reverse();
+
+    match counts.as_slice() {
+        [5] => Some(Type::FiveOfAKind),
+        [4, 1] => Some(Type::FourOfAKind),
+        [3, 2] => Some(Type::FullHouse),
+        [3, 1, 1] => Some(Type::ThreeOfAKind),
+        [2, 2, 1] => Some(Type::TwoPair),
+        [2, 1, 1, 1] => Some(Type::OnePair),
+        [1, 1, 1, 1, 1] => Some(Type::HighCard),
+        _ => None,
+    }
+}
+
+// jokers do best copying the most common other card, the strongest on a tie
+fn joker_target(cards: &str) -> char {
+    let counts = cards.chars().filter(|c| *c != 'J').collect::<Counter<_>>();
+    counts
+        .iter()
+        .max_by_key(|(c, n)| (**n, card_rank(**c)))
+        .map_or('A', |(c, _)| *c)
+}
+
+fn report(hands: &[Hand]) -> String {
+    let mut out = format!(
+        "{:>5}  {:<5}  {:<13}  {:<5}  {:<13}  {:>5}  {:>8}\n",
+        "rank", "cards", "type", "joker", "with jokers", "bid", "winnings"
+    );
+    for (i, h) in hands.iter().enumerate() {
+        let before = natural_type(h.cards).expect("weird hand");
+        let joker = if h.cards.contains('J') {
+            joker_target(h.cards).to_string()
+        } else {
+            "-".to_string()
+        };
+        let tie = [i.checked_sub(1), Some(i + 1)]
+            .iter()
+            .flatten()
+            .any(|k| hands.get(*k).is_some_and(|other| other.rank == h.rank));
+        out += &format!(
+            "{:>5}  {:<5}  {:<13}  {:<5}  {:<13}  {:>5}  {:>8}{}\n",
+            i + 1,
+            h.cards,
+            format!("{before:?}"),
+            joker,
+            format!("{:?}", h.t),
+            h.bid,
+            (i + 1) as i32 * h.bid,
+            if tie { "  tie!" } else { "" }
+        );
+    }
+    out
+}
+
+#[test]
+fn test_report() {
+    let input = "32T3K 765
+T55J5 684
+KK677 28
+KTJJT 220
+QQQJA 483
+";
+    let mut hands: Vec<Hand> = input.lines().map(Hand::new).collect();
+    hands.sort_by_key(|h| h.rank);
+
+    for h in &hands {
+        let substituted = h.cards.replace('J', &joker_target(h.cards).to_string());
+        assert_eq!(natural_type(&substituted), Some(h.t.clone()));
+    }
+
+    let out = report(&hands);
+    let rows: Vec<&str> = out.lines().collect();
+    assert_eq!(
+        rows[1],
+        "    1  32T3K  OnePair        -      OnePair          765       765"
+    );
+    assert_eq!(
+        rows[5],
+        "    5  KTJJT  TwoPair        T      FourOfAKind      220      1100"
+    );
+    assert!(!out.contains("tie!"));
+
+    let mut hands: Vec<Hand> = ["JJJJJ 1", "JJJJJ 2"].into_iter().map(Hand::new).collect();
+    hands.sort_by_key(|h| h.rank);
+    assert!(report(&hands).contains("  A      FiveOfAKind        1         1  tie!"));
+}
+
 fn main() {
     let input = fs::read_to_string("input").expect("could not read input");
     let mut hands: Vec<Hand> = input.lines().map(Hand::new).collect();
@@ -118,5 +214,9 @@ fn main() {
         .enumerate()
         .map(|(i, h)| (i + 1) as i32 * h.bid)
         .sum();
+
+    if env::args().any(|a| a == "--report") {
+        print!("{}", report(&hands));
+    }
     println!("{answer}");
 }
@@ -1,106 +1,262 @@
 use once_cell::sync::Lazy;
 use regex::Regex;
-use rustc_hash::FxHashMap;
-use std::fs;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::{env, fs};
 
 #[derive(Debug)]
 struct Node<'a> {
-    left: &'a str,
-    right: &'a str,
+    successors: Vec<&'a str>,
 }
 
-#[derive(Debug)]
-enum Direction {
-    Left,
-    Right,
-}
+// each instruction is the index of the successor to take, written either as
+// a number or as a letter from `alphabet`
+fn parse_instructions(line: &str, alphabet: &str) -> Result<Vec<usize>, String> {
+    let index = |t: &str| {
+        t.parse::<usize>()
+            .ok()
+            .or_else(|| alphabet.chars().position(|a| t.chars().eq([a])))
+            .ok_or_else(|| format!("instruction {t:?} is neither an index nor one of {alphabet:?}"))
+    };
 
-impl Direction {
-    fn new(d: char) -> Option<Direction> {
-        match d {
-            'L' => Some(Direction::Left),
-            'R' => Some(Direction::Right),
-            _ => None,
-        }
+    // separators are only needed once indices run past 9
+    if line.contains([',', ' ']) {
+        line.split([',', ' '])
+            .filter(|t| !t.is_empty())
+            .map(index)
+            .collect()
+    } else {
+        line.chars()
+            .map(|c| index(c.encode_utf8(&mut [0; 4])))
+            .collect()
     }
 }
 
-fn parse_node(line: &str) -> (&str, Node) {
-    static PATTERN: Lazy<Regex> =
-        Lazy::new(|| Regex::new(r"^([0-9A-Z]{3}) = \(([0-9A-Z]{3}), ([0-9A-Z]{3})\)$").unwrap());
+fn parse_node(line: &str) -> Option<(&str, Node<'_>)> {
+    static PATTERN: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(\w+) = \(([\w, ]*)\)$").unwrap());
 
-    let caps = PATTERN.captures(line).expect("weird node line");
+    let caps = PATTERN.captures(line)?;
 
-    let key = caps.get(1).unwrap().as_str();
-    let left = caps.get(2).unwrap().as_str();
-    let right = caps.get(3).unwrap().as_str();
+    let key = caps.get(1)?.as_str();
+    let successors: Vec<&str> = caps.get(2)?.as_str().split(',').map(|s| s.trim()).collect();
+    if successors.iter().any(|s| s.is_empty()) {
+        return None;
+    }
 
-    (key, Node { left, right })
+    Some((key, Node { successors }))
 }
 
-fn parse_input<'a>(input: &'a String) -> (Vec<Direction>, FxHashMap<&'a str, Node<'a>>) {
+fn parse_input<'a>(
+    input: &'a str,
+    alphabet: &str,
+) -> Result<(Vec<usize>, FxHashMap<&'a str, Node<'a>>), String> {
     let mut lines = input.lines();
 
-    let directions_str = lines.next().expect("no directions");
-    let directions: Vec<_> = directions_str
-        .chars()
-        .map(|d| Direction::new(d).unwrap())
-        .collect();
+    let instructions_str = lines.next().ok_or("no instructions")?;
+    let instructions = parse_instructions(instructions_str, alphabet)?;
 
     lines.next();
 
-    let node_map = FxHashMap::from_iter(lines.map(parse_node));
+    let node_map = lines
+        .map(|l| parse_node(l).ok_or_else(|| format!("weird node line {l:?}")))
+        .collect::<Result<_, _>>()?;
+
+    Ok((instructions, node_map))
+}
+
+// "*Z" matches names ending in Z, "AA*" names starting with AA, and "AAA" only AAA
+fn name_matcher(pattern: &str) -> impl Fn(&str) -> bool + '_ {
+    move |name| match pattern.split_once('*') {
+        Some((prefix, suffix)) => {
+            name.len() >= prefix.len() + suffix.len()
+                && name.starts_with(prefix)
+                && name.ends_with(suffix)
+        }
+        None => name == pattern,
+    }
+}
 
-    (directions, node_map)
+fn successor<'a>(
+    nodes: &FxHashMap<&'a str, Node<'a>>,
+    node: &str,
+    i: usize,
+) -> Result<&'a str, String> {
+    let n = nodes
+        .get(node)
+        .ok_or_else(|| format!("no node named {node}"))?;
+    n.successors
+        .get(i)
+        .copied()
+        .ok_or_else(|| format!("{node} has {} successors, no {i}", n.successors.len()))
 }
 
-fn follow_map(directions: Vec<Direction>, nodes: FxHashMap<&str, Node>) -> i64 {
-    let mut current_nodes: Vec<_> = nodes
-        .keys()
-        .filter(|k| k.chars().nth(2).unwrap() == 'A')
-        .collect();
+// steps until the walk from `start` first reaches a goal; once a node comes up
+// again at the same instruction the walk only repeats itself, so no goal is coming
+fn first_goal<G>(
+    instructions: &[usize],
+    nodes: &FxHashMap<&str, Node>,
+    start: &str,
+    is_goal: G,
+) -> Result<usize, String>
+where
+    G: Fn(&str) -> bool,
+{
+    let mut seen = FxHashSet::default();
+    let mut current = start;
+    for (steps, (k, i)) in instructions.iter().enumerate().cycle().enumerate() {
+        if is_goal(current) {
+            return Ok(steps);
+        }
+        if !seen.insert((current, k)) {
+            return Err(format!(
+                "no goal reachable from {start}: {current} repeats at instruction {k}"
+            ));
+        }
+        current = successor(nodes, current, *i)?;
+    }
+    Err("no instructions".to_string())
+}
+
+fn follow_map<S, G>(
+    instructions: &[usize],
+    nodes: &FxHashMap<&str, Node>,
+    is_start: S,
+    is_goal: G,
+) -> Result<i64, String>
+where
+    S: Fn(&str) -> bool,
+    G: Fn(&str) -> bool,
+{
+    let mut current_nodes: Vec<&str> = nodes.keys().copied().filter(|k| is_start(k)).collect();
+    if current_nodes.is_empty() {
+        return Err("no start nodes".to_string());
+    }
+    for start in &current_nodes {
+        first_goal(instructions, nodes, start, &is_goal)?;
+    }
 
     let mut steps: i64 = 0;
-    for d in directions.iter().cycle() {
-        if current_nodes
-            .iter()
-            .all(|n| n.chars().nth(2).unwrap() == 'Z')
-        {
+    for i in instructions.iter().cycle() {
+        if current_nodes.iter().all(|n| is_goal(n)) {
             break;
         }
 
-        // if steps >= 1000 {
-        //     break;
-        // }
-
         if steps % 50_000_000 == 0 {
             println!("steps: {steps}");
         }
 
-        for i in 0..current_nodes.len() {
-            let n = nodes.get(current_nodes[i]).unwrap();
-            current_nodes[i] = match d {
-                Direction::Left => &n.left,
-                Direction::Right => &n.right,
-            }
+        for current in current_nodes.iter_mut() {
+            *current = successor(nodes, current, *i)?;
         }
 
         steps += 1;
     }
 
-    println!("nodes: {:?}", current_nodes);
+    Ok(steps)
+}
+
+#[test]
+fn test_follow_map() {
+    let input = "LR
+
+11A = (11B, XXX)
+11B = (XXX, 11Z)
+11Z = (11B, XXX)
+22A = (22B, XXX)
+22B = (22C, 22C)
+22C = (22Z, 22Z)
+22Z = (22B, 22B)
+XXX = (XXX, XXX)
+";
+    let (instructions, nodes) = parse_input(input, "LR").unwrap();
+    assert_eq!(instructions, vec![0, 1]);
+    assert_eq!(
+        follow_map(
+            &instructions,
+            &nodes,
+            name_matcher("*A"),
+            name_matcher("*Z")
+        ),
+        Ok(6)
+    );
 
-    return steps;
+    // longer names, three successors and numbered instructions
+    let input = "2, 0, 1
+
+start = (left, right, middle)
+left = (start, start, start)
+right = (start, start, start)
+middle = (end, start, start)
+end = (end, end, end)
+";
+    let (instructions, nodes) = parse_input(input, "LR").unwrap();
+    assert_eq!(instructions, vec![2, 0, 1]);
+    assert_eq!(
+        follow_map(
+            &instructions,
+            &nodes,
+            name_matcher("start"),
+            name_matcher("e*d")
+        ),
+        Ok(2)
+    );
+
+    assert_eq!(parse_instructions("LMR", "LMR"), Ok(vec![0, 1, 2]));
+    assert_eq!(parse_instructions("0120", "LR"), Ok(vec![0, 1, 2, 0]));
+    assert!(parse_instructions("LX", "LR").is_err());
+    assert!(follow_map(&[3], &nodes, name_matcher("start"), |_| false).is_err());
+    assert_eq!(
+        follow_map(
+            &instructions,
+            &nodes,
+            name_matcher("start"),
+            name_matcher("nowhere")
+        ),
+        Err("no goal reachable from start: end repeats at instruction 2".to_string())
+    );
+    assert!(parse_node("AAA = (BBB, )").is_none());
+
+    // letters are counted in characters, not bytes
+    assert_eq!(parse_instructions("→←→", "←→"), Ok(vec![1, 0, 1]));
+    let input = input.replacen("2, 0, 1", "→←↓", 1);
+    let (instructions, nodes) = parse_input(&input, "←↓→").unwrap();
+    assert_eq!(instructions, vec![2, 0, 1]);
+    assert_eq!(
+        follow_map(
+            &instructions,
+            &nodes,
+            name_matcher("start"),
+            name_matcher("end")
+        ),
+        Ok(2)
+    );
+    assert!(!name_matcher("AB*BA")("ABA"));
 }
 
 fn main() {
-    let input = fs::read_to_string("input").expect("unable to read input");
+    let args: Vec<String> = env::args().collect();
+    let option = |flag: &str, default: &'static str| -> String {
+        match args.iter().position(|a| a == flag) {
+            Some(k) => args
+                .get(k + 1)
+                .unwrap_or_else(|| panic!("{flag} takes a value"))
+                .to_string(),
+            None => default.to_string(),
+        }
+    };
+    let alphabet = option("--alphabet", "LR");
+    let start = option("--start", "*A");
+    let goal = option("--goal", "*Z");
 
-    let (directions, nodes) = parse_input(&input);
-    let answer = follow_map(directions, nodes);
+    let input = fs::read_to_string("input").expect("unable to read input");
 
-    // println!("directions: {:?}", directions);
-    // println!("nodes: {:?}", nodes);
+    let (instructions, nodes) = parse_input(&input, &alphabet).expect("unable to parse input");
+    let answer = follow_map(
+        &instructions,
+        &nodes,
+        name_matcher(&start),
+        name_matcher(&goal),
+    )
+    .expect("unable to follow map");
 
     println!("answer: {answer}");
 }
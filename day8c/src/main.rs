@@ -1,89 +1,213 @@
 use once_cell::sync::Lazy;
 use regex::Regex;
-use std::{collections::HashMap, fs};
+use std::{collections::HashMap, env, fs};
 
 #[derive(Debug)]
 struct Node<'a> {
-    left: &'a str,
-    right: &'a str,
+    successors: Vec<&'a str>,
 }
 
-#[derive(Debug)]
-enum Direction {
-    Left,
-    Right,
-}
-
-impl Direction {
-    fn new(d: char) -> Option<Direction> {
-        match d {
-            'L' => Some(Direction::Left),
-            'R' => Some(Direction::Right),
-            _ => None,
-        }
+// each instruction is the index of the successor to take, written either as
+// a number or as a letter from `alphabet`
+fn parse_instructions(line: &str, alphabet: &str) -> Result<Vec<usize>, String> {
+    let index = |t: &str| {
+        t.parse::<usize>()
+            .ok()
+            .or_else(|| alphabet.chars().position(|a| t.chars().eq([a])))
+            .ok_or_else(|| format!("instruction {t:?} is neither an index nor one of {alphabet:?}"))
+    };
+
+    // separators are only needed once indices run past 9
+    if line.contains([',', ' ']) {
+        line.split([',', ' '])
+            .filter(|t| !t.is_empty())
+            .map(index)
+            .collect()
+    } else {
+        line.chars()
+            .map(|c| index(c.encode_utf8(&mut [0; 4])))
+            .collect()
     }
 }
 
-fn parse_node(line: &str) -> (&str, Node) {
-    static PATTERN: Lazy<Regex> =
-        Lazy::new(|| Regex::new(r"^([A-Z]{3}) = \(([A-Z]{3}), ([A-Z]{3})\)$").unwrap());
+fn parse_node(line: &str) -> Option<(&str, Node<'_>)> {
+    static PATTERN: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(\w+) = \(([\w, ]*)\)$").unwrap());
 
-    let caps = PATTERN.captures(line).expect("weird node line");
+    let caps = PATTERN.captures(line)?;
 
-    let key = caps.get(1).unwrap().as_str();
-    let left = caps.get(2).unwrap().as_str();
-    let right = caps.get(3).unwrap().as_str();
+    let key = caps.get(1)?.as_str();
+    let successors: Vec<&str> = caps.get(2)?.as_str().split(',').map(|s| s.trim()).collect();
+    if successors.iter().any(|s| s.is_empty()) {
+        return None;
+    }
 
-    (key, Node { left, right })
+    Some((key, Node { successors }))
 }
 
-fn parse_input<'a>(input: &'a String) -> (Vec<Direction>, HashMap<&'a str, Node<'a>>) {
+fn parse_input<'a>(
+    input: &'a str,
+    alphabet: &str,
+) -> Result<(Vec<usize>, HashMap<&'a str, Node<'a>>), String> {
     let mut lines = input.lines();
 
-    let directions_str = lines.next().expect("no directions");
-    let directions: Vec<_> = directions_str
-        .chars()
-        .map(|d| Direction::new(d).unwrap())
-        .collect();
+    let instructions_str = lines.next().ok_or("no instructions")?;
+    let instructions = parse_instructions(instructions_str, alphabet)?;
 
     lines.next();
 
-    let node_map = HashMap::from_iter(lines.map(parse_node));
+    let node_map = lines
+        .map(|l| parse_node(l).ok_or_else(|| format!("weird node line {l:?}")))
+        .collect::<Result<_, _>>()?;
+
+    Ok((instructions, node_map))
+}
+
+// "*Z" matches names ending in Z, "AA*" names starting with AA, and "AAA" only AAA
+fn name_matcher(pattern: &str) -> impl Fn(&str) -> bool + '_ {
+    move |name| match pattern.split_once('*') {
+        Some((prefix, suffix)) => {
+            name.len() >= prefix.len() + suffix.len()
+                && name.starts_with(prefix)
+                && name.ends_with(suffix)
+        }
+        None => name == pattern,
+    }
+}
 
-    (directions, node_map)
+fn successor<'a>(
+    nodes: &HashMap<&'a str, Node<'a>>,
+    node: &str,
+    i: usize,
+) -> Result<&'a str, String> {
+    let n = nodes
+        .get(node)
+        .ok_or_else(|| format!("no node named {node}"))?;
+    n.successors
+        .get(i)
+        .copied()
+        .ok_or_else(|| format!("{node} has {} successors, no {i}", n.successors.len()))
 }
 
-fn follow_map(directions: Vec<Direction>, nodes: HashMap<&str, Node>) -> i32 {
-    let mut current_node = "NNA";
-    let mut steps = 0;
-    let mut visited = 0;
-    for d in directions.iter().cycle() {
-        if current_node.chars().nth(2).unwrap() == 'Z' {
-            println!("{current_node}\t{steps}");
-            if visited >= 10 {
-                break;
+// the first `count` goal nodes reached from `start`, with the step each was
+// reached at; once a node comes up again at the same instruction the walk only
+// repeats itself, so if no goal turned up since its first visit none ever will
+fn goal_hits<'a, G>(
+    instructions: &[usize],
+    nodes: &HashMap<&'a str, Node<'a>>,
+    start: &'a str,
+    is_goal: G,
+    count: usize,
+) -> Result<Vec<(&'a str, usize)>, String>
+where
+    G: Fn(&str) -> bool,
+{
+    let mut hits: Vec<(&str, usize)> = Vec::new();
+    let mut seen: HashMap<(&str, usize), usize> = HashMap::new();
+    let mut current_node = start;
+    for (steps, (k, i)) in instructions.iter().enumerate().cycle().enumerate() {
+        if is_goal(current_node) {
+            hits.push((current_node, steps));
+            if hits.len() >= count {
+                return Ok(hits);
             }
-            visited += 1;
         }
 
-        let n = nodes.get(current_node).unwrap();
-        current_node = match d {
-            Direction::Left => n.left,
-            Direction::Right => n.right,
-        };
-        steps += 1;
+        let first = *seen.entry((current_node, k)).or_insert(steps);
+        if first < steps && hits.last().is_none_or(|(_, h)| *h < first) {
+            return Err(format!(
+                "no goal reachable from {start}: {current_node} repeats at instruction {k}"
+            ));
+        }
+
+        current_node = successor(nodes, current_node, *i)?;
     }
-    return steps;
+    Err("no instructions".to_string())
+}
+
+#[test]
+fn test_goal_hits() {
+    let input = "LR
+
+11A = (11B, XXX)
+11B = (XXX, 11Z)
+11Z = (11B, XXX)
+22A = (22B, XXX)
+22B = (22C, 22C)
+22C = (22Z, 22Z)
+22Z = (22B, 22B)
+XXX = (XXX, XXX)
+";
+    let (instructions, nodes) = parse_input(input, "LR").unwrap();
+    assert_eq!(
+        goal_hits(&instructions, &nodes, "22A", name_matcher("*Z"), 3),
+        Ok(vec![("22Z", 3), ("22Z", 6), ("22Z", 9)])
+    );
+    assert_eq!(
+        goal_hits(&instructions, &nodes, "11A", name_matcher("11Z"), 2),
+        Ok(vec![("11Z", 2), ("11Z", 4)])
+    );
+
+    let input = "0 2
+
+a = (b, c, d)
+b = (a, a, d)
+c = (a, a, a)
+d = (a, a, a)
+";
+    let (instructions, nodes) = parse_input(input, "").unwrap();
+    assert_eq!(
+        goal_hits(&instructions, &nodes, "a", name_matcher("d"), 2),
+        Ok(vec![("d", 2), ("d", 4)])
+    );
+    assert!(goal_hits(&[1], &nodes, "z", |_| false, 1).is_err());
+    assert_eq!(
+        goal_hits(&instructions, &nodes, "a", name_matcher("e"), 1),
+        Err("no goal reachable from a: d repeats at instruction 0".to_string())
+    );
+
+    // letters are counted in characters, not bytes
+    assert_eq!(parse_instructions("→←→", "←→"), Ok(vec![1, 0, 1]));
+    let input = input.replacen("0 2", "←→", 1);
+    let (instructions, nodes) = parse_input(&input, "←↓→").unwrap();
+    assert_eq!(instructions, vec![0, 2]);
+    assert_eq!(
+        goal_hits(&instructions, &nodes, "a", name_matcher("d"), 2),
+        Ok(vec![("d", 2), ("d", 4)])
+    );
 }
 
 fn main() {
-    let input = fs::read_to_string("input").expect("unable to read input");
+    let args: Vec<String> = env::args().collect();
+    let option = |flag: &str, default: &'static str| -> String {
+        match args.iter().position(|a| a == flag) {
+            Some(k) => args
+                .get(k + 1)
+                .unwrap_or_else(|| panic!("{flag} takes a value"))
+                .to_string(),
+            None => default.to_string(),
+        }
+    };
+    let alphabet = option("--alphabet", "LR");
+    let start = option("--start", "NNA");
+    let goal = option("--goal", "*Z");
 
-    let (directions, nodes) = parse_input(&input);
-    let answer = follow_map(directions, nodes);
+    let input = fs::read_to_string("input").expect("unable to read input");
 
-    // println!("directions: {:?}", directions);
-    // println!("nodes: {:?}", nodes);
+    let (instructions, nodes) = parse_input(&input, &alphabet).expect("unable to parse input");
+    let is_start = name_matcher(&start);
+    let mut starts: Vec<&str> = nodes.keys().copied().filter(|k| is_start(k)).collect();
+    starts.sort();
 
-    println!("answer: {answer}");
+    for s in &starts {
+        if starts.len() > 1 {
+            println!("{s}");
+        }
+        let hits = goal_hits(&instructions, &nodes, s, name_matcher(&goal), 11)
+            .expect("unable to follow map");
+        for (node, steps) in &hits {
+            println!("{node}\t{steps}");
+        }
+        let answer = hits.last().map_or(0, |(_, steps)| *steps);
+        println!("answer: {answer}");
+    }
 }
@@ -1,4 +1,4 @@
-use std::{fs, mem};
+use std::{env, fs, mem};
 
 fn parse_line(line: &str) -> Vec<i32> {
     line.split_whitespace()
@@ -21,7 +21,7 @@ impl Iterator for Sequences {
     type Item = Vec<i32>;
 
     fn next(&mut self) -> Option<Vec<i32>> {
-        let mut next: Vec<_> = self.next.windows(2).map(|w| &w[1] - &w[0]).collect();
+        let mut next: Vec<_> = self.next.windows(2).map(|w| w[1] - w[0]).collect();
         mem::swap(&mut next, &mut self.next);
         // println!("{:?}", next);
         Some(next)
@@ -44,9 +44,171 @@ fn predict_next(sequence: Vec<i32>) -> i32 {
     odds - evens
 }
 
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn format_fraction(n: i128, d: i128) -> String {
+    let g = gcd(n, d).max(1);
+    let (n, d) = (n / g * d.signum(), d.abs() / g);
+    if d == 1 {
+        format!("{n}")
+    } else {
+        format!("{n}/{d}")
+    }
+}
+
+// the polynomial through a history, with the first value at x = 0; every
+// coefficient is numerators[j] / denominator
+#[derive(Debug)]
+struct Fit {
+    degree: usize,
+    reaches_zero: bool,
+    numerators: Vec<i128>,
+    denominator: i128,
+}
+
+impl Fit {
+    fn new(sequence: &[i32]) -> Self {
+        let sequences: Vec<_> = Sequences::new(sequence.to_vec())
+            .take_while(|s| !s.iter().all(|x| x == &0))
+            .collect();
+        // the last row of differences has a single value, so a history that
+        // never hits a row of zeros ends on the empty row instead
+        let reaches_zero = sequences.len() < sequence.len();
+        let degree = sequences.len().saturating_sub(1);
+
+        // Newton's forward form: sum over k of (first value of row k) * C(x, k),
+        // scaled by degree! so the binomials have integer coefficients
+        let denominator: i128 = (1..=degree as i128).product();
+        let mut numerators = vec![0; degree + 1];
+        let mut falling = vec![1i128];
+        let mut factorial = 1;
+        for (k, row) in sequences.iter().enumerate() {
+            if k > 0 {
+                factorial *= k as i128;
+                // falling *= (x - (k - 1))
+                let mut next = vec![0; falling.len() + 1];
+                for (j, c) in falling.iter().enumerate() {
+                    next[j + 1] += c;
+                    next[j] -= c * (k as i128 - 1);
+                }
+                falling = next;
+            }
+            let scale = row[0] as i128 * (denominator / factorial);
+            for (n, c) in numerators.iter_mut().zip(&falling) {
+                *n += scale * c;
+            }
+        }
+
+        Fit {
+            degree,
+            reaches_zero,
+            numerators,
+            denominator,
+        }
+    }
+
+    fn coefficients(&self) -> Vec<String> {
+        self.numerators
+            .iter()
+            .map(|n| format_fraction(*n, self.denominator))
+            .collect()
+    }
+
+    // None if the value is not a whole number, or too big to work out
+    fn eval(&self, x: i128) -> Option<i128> {
+        let scaled = self
+            .numerators
+            .iter()
+            .rev()
+            .try_fold(0i128, |acc, n| acc.checked_mul(x)?.checked_add(*n))?;
+        (scaled % self.denominator == 0).then_some(scaled / self.denominator)
+    }
+
+    // positions where the polynomial misses the history it was fitted to
+    fn mismatches(&self, sequence: &[i32]) -> Vec<usize> {
+        sequence
+            .iter()
+            .enumerate()
+            .filter(|(x, y)| self.eval(*x as i128) != Some(**y as i128))
+            .map(|(x, _)| x)
+            .collect()
+    }
+}
+
+fn report(input: &str) -> String {
+    let mut out = String::new();
+    for (i, line) in input.lines().enumerate() {
+        let sequence = parse_line(line);
+        let fit = Fit::new(&sequence);
+        let mismatches = fit.mismatches(&sequence);
+
+        out += &format!(
+            "{}: degree {}, previous {}, coefficients [{}]",
+            i + 1,
+            fit.degree,
+            fit.eval(-1).map_or("?".to_string(), |v| v.to_string()),
+            fit.coefficients().join(", ")
+        );
+        if !fit.reaches_zero {
+            out += ", never reaches zero";
+        }
+        if !mismatches.is_empty() {
+            out += &format!(", does not fit x = {mismatches:?}");
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[test]
+fn test_fit() {
+    let input = "0 3 6 9 12 15
+1 3 6 10 15 21
+10 13 16 21 30 45
+";
+    let predictions: Vec<i32> = input.lines().map(parse_line).map(predict_next).collect();
+    assert_eq!(predictions, vec![-3, 0, 5]);
+
+    let fits: Vec<Fit> = input.lines().map(|l| Fit::new(&parse_line(l))).collect();
+    assert_eq!(
+        fits.iter().map(|f| f.degree).collect::<Vec<_>>(),
+        vec![1, 2, 3]
+    );
+    assert_eq!(fits[0].coefficients(), vec!["0", "3"]);
+    assert_eq!(fits[1].coefficients(), vec!["1", "3/2", "1/2"]);
+    assert_eq!(fits[2].coefficients(), vec!["10", "11/3", "-1", "1/3"]);
+    for (fit, line) in fits.iter().zip(input.lines()) {
+        assert!(fit.reaches_zero);
+        assert!(fit.mismatches(&parse_line(line)).is_empty());
+    }
+    assert_eq!(fits[2].eval(6), Some(68));
+
+    // four values can't show that the third differences stay put
+    let fit = Fit::new(&[1, 2, 4, 8]);
+    assert!(!fit.reaches_zero);
+    assert_eq!(fit.degree, 3);
+
+    let fit = Fit::new(&[0, 0, 0]);
+    assert_eq!((fit.degree, fit.coefficients()), (0, vec!["0".to_string()]));
+
+    assert_eq!(
+        report("1 2 4 8\n"),
+        "1: degree 3, previous 0, coefficients [1, 5/6, 0, 1/6], never reaches zero\n"
+    );
+}
+
 fn main() {
     let input = fs::read_to_string("input").expect("unable to read input");
     let answer: i32 = input.lines().map(parse_line).map(predict_next).sum();
 
+    if env::args().any(|a| a == "--report") {
+        print!("{}", report(&input));
+    }
     println!("answer: {answer}");
 }